@@ -1,4 +1,15 @@
-use crate::{config::Config, events, state::AppState, tui::Tui, ui};
+use crate::{
+    bookmarks::{Bookmark, DocumentBookmarks},
+    color::ColorDepth,
+    config::Config,
+    document::Document,
+    events,
+    search::Search,
+    state::AppState,
+    stats::SessionStats,
+    tui::Tui,
+    ui,
+};
 use color_eyre::Result;
 use ratatui::{buffer::Buffer, layout::Rect, style::Color};
 use std::time::Instant;
@@ -75,30 +86,83 @@ fn brighten_color(color: Color) -> Color {
     }
 }
 
-pub fn run(content: &str, wpm: u64, is_inline: bool, preview_words: Option<usize>, terminal: &mut Tui) -> Result<()> {
+/// Most words shown on either side of the current word in the pause context.
+const CONTEXT_RADIUS: usize = 120;
+
+/// Words stored with a bookmark so listings show where it points.
+const BOOKMARK_EXCERPT_WORDS: usize = 6;
+
+/// A mark command (`m` or `'`) waiting for its letter.
+#[derive(Clone, Copy)]
+enum PendingMark {
+    Set,
+    Jump,
+}
+
+/// How a reading session ended.
+pub enum Outcome {
+    /// Every word was shown.
+    Finished,
+    /// The reader quit early while on `word_index`.
+    Quit { word_index: usize },
+}
+
+/// The result of a reading session.
+pub struct Session {
+    pub outcome: Outcome,
+    pub stats: SessionStats,
+}
+
+pub fn run(
+    document: &Document,
+    config: &Config,
+    wpm: u64,
+    start_word: usize,
+    bookmarks: &mut DocumentBookmarks,
+    terminal: &mut Tui,
+) -> Result<Session> {
     let mut last_frame = Instant::now();
     let mut effects: EffectManager<()> = EffectManager::default();
-    let mut app_state = AppState::new(content, wpm);
 
-    // Load config
-    let config = Config::load()?;
-    let preview_count = preview_words.unwrap_or(config.preview_words);
+    let mut app_state = AppState::new(&document.text, wpm)
+        .with_chapters(&document.chapters)
+        .with_emphasis(&document.emphasis)
+        .with_timing(config.timing.clone())
+        .with_max_word_width(config.max_word_width)
+        .with_chunk_size(config.chunk);
+    app_state.jump_to(start_word);
 
-    let ui_constraints = ui::UIConstraints::new(is_inline, preview_count);
-    let is_first_use = Config::is_first_use()?;
+    let is_inline = config.inline;
+    let preview_count = config.preview_words;
+
+    let is_first_use = Config::is_first_use();
 
     // Extract config values
+    let theme = config.resolve_theme()?;
+    // crossterm drops all colors when NO_COLOR is set; an explicit
+    // `color_mode` has already had the final say
+    crossterm::style::force_color_output(theme.depth != ColorDepth::NoColor);
     let border_color = if is_inline && config.show_border {
-        Some(config.parse_border_color())
+        Some(theme.border)
     } else {
         None
     };
-    let progress_bar_color = config.parse_progress_bar_color();
-    let focus_color = config.parse_focus_color();
     let enable_animations = config.enable_animations;
     let show_border = config.show_border;
     let show_progress_bar = config.show_progress_bar;
     let seek_step = config.seek_step as isize;
+    let wpm_step = config.wpm_step as i64;
+    let keymap = config.keymap()?;
+    let help_rows = keymap.help_rows(config.seek_step, config.wpm_step);
+    let display = ui::DisplayOptions {
+        constraints: ui::UIConstraints::new(is_inline, preview_count),
+        is_inline,
+        border_color,
+        enable_animations,
+        show_border,
+        show_progress_bar,
+        reorder_rtl: config.reorder_rtl,
+    };
 
     // Border animation setup (only if animations are enabled)
     let border_animation_duration_ms = 600.0; // 0.6 seconds for full animation
@@ -111,11 +175,18 @@ pub fn run(content: &str, wpm: u64, is_inline: bool, preview_words: Option<usize
     // Help overlay state
     let mut show_help = false;
     let mut help_scroll: u16 = 0;
-    let help_border_color = config.parse_border_color();
+
+    // Search state: the prompt being typed, and the last submitted search
+    let mut search_input: Option<String> = None;
+    let mut search: Option<Search> = None;
+    let mut pending_mark: Option<PendingMark> = None;
+
+    let mut stats = SessionStats::default();
 
     // Mark first use as complete
     if is_first_use && is_inline {
-        Config::mark_first_use_complete()?;
+        // Best effort: with a read-only state directory this just runs again
+        Config::mark_first_use_complete().ok();
     }
 
     loop {
@@ -139,25 +210,37 @@ pub fn run(content: &str, wpm: u64, is_inline: bool, preview_words: Option<usize
             };
 
             // Render UI and get progress bar area for effects
+            let (word, continues) = app_state.current_part().unwrap_or_default();
             let preview = app_state.peek_words(preview_count);
-            let progress_area = ui::render_word_display(
-                f,
-                app_state.current_word().unwrap_or(""),
-                &preview,
-                app_state.current_word_index(),
-                app_state.total_words(),
-                app_state.is_paused(),
-                &ui_constraints,
-                is_inline,
-                border_color,
+            let context = app_state.is_paused().then(|| {
+                let (words, current) = app_state.context_words(is_inline, CONTEXT_RADIUS);
+                ui::Context { words, current }
+            });
+            // The status row shows the search prompt or results, else the chapter
+            let status = match (pending_mark, &search_input, &search) {
+                (Some(PendingMark::Set), _, _) => Some("set mark (a-z)".to_string()),
+                (Some(PendingMark::Jump), _, _) => Some("jump to mark (a-z)".to_string()),
+                (None, Some(input), _) => Some(format!("/{}▏", input)),
+                (None, None, Some(search)) => Some(search.status()),
+                (None, None, None) => {
+                    app_state.current_chapter().map(|chapter| chapter.title.clone())
+                }
+            };
+            let view = ui::WordView {
+                word: &word,
+                continues,
+                emphasis: app_state.current_emphasis(),
+                preview_words: &preview,
+                context: context.as_ref(),
+                current_word: app_state.current_word_index(),
+                total_words: app_state.total_words(),
+                wpm: app_state.wpm(),
+                status: status.as_deref(),
+                is_paused: app_state.is_paused(),
                 border_progress,
                 time_ms,
-                progress_bar_color,
-                focus_color,
-                enable_animations,
-                show_border,
-                show_progress_bar,
-            );
+            };
+            let progress_area = ui::render_word_display(f, &view, &display, &theme);
 
             // Apply scanner sweep effect to progress bar (only if animations enabled)
             if enable_animations && show_progress_bar {
@@ -173,22 +256,36 @@ pub fn run(content: &str, wpm: u64, is_inline: bool, preview_words: Option<usize
 
             // Render help popup on top of everything else
             if show_help {
-                ui::render_help_popup(f, help_border_color, help_scroll, config.seek_step);
+                ui::render_help_popup(f, &theme, help_scroll, &help_rows, &keymap);
             }
+
+            theme.depth.apply(f.buffer_mut());
         })?;
 
         let timeout = app_state.get_timeout();
 
-        match events::handle_events(timeout)? {
+        let text_input = search_input.is_some() || pending_mark.is_some();
+        match events::handle_events(timeout, text_input, &keymap)? {
             events::AppEvent::Quit => {
                 if show_help {
                     show_help = false;
                     help_scroll = 0;
                 } else {
-                    break;
+                    stats.elapsed = session_start.elapsed();
+                    return Ok(Session {
+                        outcome: Outcome::Quit {
+                            word_index: app_state.current_word_index(),
+                        },
+                        stats,
+                    });
+                }
+            }
+            events::AppEvent::TogglePause => {
+                app_state.toggle_pause();
+                if app_state.is_paused() {
+                    stats.pauses += 1;
                 }
             }
-            events::AppEvent::TogglePause => app_state.toggle_pause(),
             events::AppEvent::ToggleHelp => {
                 show_help = !show_help;
                 if !show_help {
@@ -211,8 +308,119 @@ pub fn run(content: &str, wpm: u64, is_inline: bool, preview_words: Option<usize
                 }
             }
             events::AppEvent::Rewind => {
-                if !show_help {
+                // While paused, rewind re-orients to the start of the sentence
+                // rather than an arbitrary word `seek_step` back
+                if !show_help && app_state.is_paused() {
+                    app_state.prev_sentence();
+                    stats.rewinds += 1;
+                } else if !show_help {
                     app_state.seek_word(-seek_step);
+                    stats.rewinds += 1;
+                }
+            }
+            events::AppEvent::SpeedUp => app_state.adjust_wpm(wpm_step),
+            events::AppEvent::SlowDown => app_state.adjust_wpm(-wpm_step),
+            events::AppEvent::PrevSentence => {
+                if !show_help {
+                    app_state.prev_sentence();
+                    stats.rewinds += 1;
+                }
+            }
+            events::AppEvent::NextSentence => {
+                if !show_help {
+                    app_state.next_sentence();
+                }
+            }
+            events::AppEvent::PrevParagraph => {
+                if !show_help {
+                    app_state.prev_paragraph();
+                    stats.rewinds += 1;
+                }
+            }
+            events::AppEvent::NextParagraph => {
+                if !show_help {
+                    app_state.next_paragraph();
+                }
+            }
+            events::AppEvent::StartSearch => {
+                if !show_help {
+                    search_input = Some(String::new());
+                }
+            }
+            events::AppEvent::SetMark => {
+                if !show_help {
+                    pending_mark = Some(PendingMark::Set);
+                }
+            }
+            events::AppEvent::JumpToMark => {
+                if !show_help {
+                    pending_mark = Some(PendingMark::Jump);
+                }
+            }
+            events::AppEvent::InputChar(c) => match pending_mark.take() {
+                Some(PendingMark::Set) if c.is_ascii_lowercase() => {
+                    let index = app_state.current_word_index();
+                    let mut excerpt = vec![app_state.current_word().unwrap_or("")];
+                    excerpt.extend(app_state.peek_words(BOOKMARK_EXCERPT_WORDS - 1));
+                    bookmarks.marks.insert(
+                        c.to_string(),
+                        Bookmark {
+                            word: index,
+                            excerpt: excerpt.join(" "),
+                        },
+                    );
+                }
+                Some(PendingMark::Jump) if c.is_ascii_lowercase() => {
+                    if let Some(mark) = bookmarks.marks.get(&c.to_string()) {
+                        // Going back to a mark is a rewind like any other
+                        if mark.word < app_state.current_word_index() {
+                            stats.rewinds += 1;
+                        }
+                        app_state.jump_to(mark.word);
+                    }
+                }
+                // Marks are named a-z only
+                Some(PendingMark::Set | PendingMark::Jump) => {}
+                None => {
+                    if let Some(input) = search_input.as_mut() {
+                        input.push(c);
+                    }
+                }
+            },
+            events::AppEvent::InputBackspace if pending_mark.is_some() => pending_mark = None,
+            events::AppEvent::InputBackspace => {
+                // Backspace on an empty prompt closes it, as in vim
+                if search_input.as_mut().and_then(|input| input.pop()).is_none() {
+                    search_input = None;
+                }
+            }
+            events::AppEvent::InputSubmit if pending_mark.is_some() => pending_mark = None,
+            events::AppEvent::InputSubmit => {
+                if let Some(query) = search_input.take().filter(|q| !q.trim().is_empty()) {
+                    let matches = app_state.find_matches(&query);
+                    let mut results = Search::new(query, matches);
+                    if let Some(index) = results.next_from(app_state.current_word_index()) {
+                        app_state.jump_to(index);
+                    }
+                    search = Some(results);
+                }
+            }
+            events::AppEvent::InputCancel => {
+                pending_mark = None;
+                search_input = None;
+            }
+            events::AppEvent::NextMatch => {
+                if let Some(results) = search.as_mut().filter(|_| !show_help) {
+                    if let Some(index) = results.next_from(app_state.current_word_index()) {
+                        app_state.jump_to(index);
+                    }
+                }
+            }
+            events::AppEvent::PrevMatch => {
+                if let Some(results) = search.as_mut().filter(|_| !show_help) {
+                    if let Some(index) = results.prev_from(app_state.current_word_index()) {
+                        app_state.jump_to(index);
+                    }
                 }
             }
             events::AppEvent::Continue => {}
@@ -226,14 +434,31 @@ pub fn run(content: &str, wpm: u64, is_inline: bool, preview_words: Option<usize
             true // No animation, proceed immediately
         };
 
-        // Only advance words after animation completes and help is not shown
-        if animation_complete && !show_help && app_state.should_advance() && !app_state.advance_word() {
-            break; // Reading complete
+        // Only advance words after animation completes and no overlay or prompt is
+        // open, including a mark command waiting for its letter
+        let overlay_open = show_help || search_input.is_some() || pending_mark.is_some();
+        // Long words shown in parts finish their parts before moving on
+        if animation_complete
+            && !overlay_open
+            && app_state.should_advance()
+            && !app_state.advance_part()
+        {
+            stats.words_read += app_state.chunk_len();
+            if !app_state.advance_word() {
+                stats.elapsed = session_start.elapsed();
+                terminal.draw(|f| {
+                    ui::render_summary(f, &stats, &theme);
+                    theme.depth.apply(f.buffer_mut());
+                })?;
+                events::wait_for_key()?;
+                return Ok(Session {
+                    outcome: Outcome::Finished,
+                    stats,
+                });
+            }
         }
 
         // Reset frame timer for next iteration
         last_frame = Instant::now();
     }
-
-    Ok(())
 }
@@ -0,0 +1,293 @@
+use std::{borrow::Cow, iter};
+use unicode_bidi::{Direction, Level, ParagraphBidiInfo};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Arabic letters and their isolated presentation form. Letters with two
+/// forms only join to the letter before them; those with four join on both
+/// sides. The forms follow the isolated one in the order isolated, final,
+/// initial, medial.
+const ARABIC_FORMS: [(char, char, u8); 42] = [
+    ('\u{0621}', '\u{FE80}', 1),
+    ('\u{0622}', '\u{FE81}', 2),
+    ('\u{0623}', '\u{FE83}', 2),
+    ('\u{0624}', '\u{FE85}', 2),
+    ('\u{0625}', '\u{FE87}', 2),
+    ('\u{0626}', '\u{FE89}', 4),
+    ('\u{0627}', '\u{FE8D}', 2),
+    ('\u{0628}', '\u{FE8F}', 4),
+    ('\u{0629}', '\u{FE93}', 2),
+    ('\u{062A}', '\u{FE95}', 4),
+    ('\u{062B}', '\u{FE99}', 4),
+    ('\u{062C}', '\u{FE9D}', 4),
+    ('\u{062D}', '\u{FEA1}', 4),
+    ('\u{062E}', '\u{FEA5}', 4),
+    ('\u{062F}', '\u{FEA9}', 2),
+    ('\u{0630}', '\u{FEAB}', 2),
+    ('\u{0631}', '\u{FEAD}', 2),
+    ('\u{0632}', '\u{FEAF}', 2),
+    ('\u{0633}', '\u{FEB1}', 4),
+    ('\u{0634}', '\u{FEB5}', 4),
+    ('\u{0635}', '\u{FEB9}', 4),
+    ('\u{0636}', '\u{FEBD}', 4),
+    ('\u{0637}', '\u{FEC1}', 4),
+    ('\u{0638}', '\u{FEC5}', 4),
+    ('\u{0639}', '\u{FEC9}', 4),
+    ('\u{063A}', '\u{FECD}', 4),
+    ('\u{0641}', '\u{FED1}', 4),
+    ('\u{0642}', '\u{FED5}', 4),
+    ('\u{0643}', '\u{FED9}', 4),
+    ('\u{0644}', '\u{FEDD}', 4),
+    ('\u{0645}', '\u{FEE1}', 4),
+    ('\u{0646}', '\u{FEE5}', 4),
+    ('\u{0647}', '\u{FEE9}', 4),
+    ('\u{0648}', '\u{FEED}', 2),
+    ('\u{0649}', '\u{FEEF}', 2),
+    ('\u{064A}', '\u{FEF1}', 4),
+    // Persian and Urdu
+    ('\u{067E}', '\u{FB56}', 4),
+    ('\u{0686}', '\u{FB7A}', 4),
+    ('\u{0698}', '\u{FB8A}', 2),
+    ('\u{06A9}', '\u{FB8E}', 4),
+    ('\u{06AF}', '\u{FB92}', 4),
+    ('\u{06CC}', '\u{FBFC}', 4),
+];
+
+/// Lam followed by one of these alefs is written as one ligature, given here
+/// in its isolated form; the final form follows it.
+const LAM_ALEF: [(char, char); 4] = [
+    ('\u{0622}', '\u{FEF5}'),
+    ('\u{0623}', '\u{FEF7}'),
+    ('\u{0625}', '\u{FEF9}'),
+    ('\u{0627}', '\u{FEFB}'),
+];
+
+const LAM: char = '\u{0644}';
+const TATWEEL: char = '\u{0640}';
+
+/// Whether `text` reads right to left: its first letter with a direction is
+/// Hebrew, Arabic or another RTL script. `None` when it has no such letter,
+/// as with numbers and punctuation.
+pub fn is_rtl(text: &str) -> Option<bool> {
+    match unicode_bidi::get_base_direction(text) {
+        Direction::Ltr => Some(false),
+        Direction::Rtl => Some(true),
+        Direction::Mixed => None,
+    }
+}
+
+/// `text` as it should be drawn on a terminal that neither shapes nor
+/// reorders: Arabic letters in their joined forms, and the whole in
+/// left-to-right display order.
+pub fn visual(text: &str) -> String {
+    visual_graphemes(text)
+        .into_iter()
+        .map(|(_, grapheme)| grapheme)
+        .collect()
+}
+
+/// The grapheme clusters of `text`, shaped and in left-to-right display
+/// order per the Unicode bidi algorithm, each with its index in logical
+/// order. Brackets in right-to-left runs are mirrored.
+pub fn visual_graphemes(text: &str) -> Vec<(usize, String)> {
+    reorder(&shape(text), None)
+}
+
+/// A line of `words` joined by spaces, as [`visual_graphemes`] gives it for
+/// a paragraph that reads right to left if `rtl`. Each grapheme comes with
+/// the index of the word it belongs to, `None` for the spaces between.
+/// Reordering the line as a whole keeps a run of left-to-right words inside
+/// right-to-left text (or the reverse) in its own order.
+pub fn visual_line(words: &[&str], rtl: bool) -> Vec<(Option<usize>, String)> {
+    let shaped: Vec<Cow<str>> = words.iter().map(|word| shape(word)).collect();
+    let mut owners = Vec::new();
+    for (i, word) in shaped.iter().enumerate() {
+        if i > 0 {
+            owners.push(None);
+        }
+        owners.extend(iter::repeat_n(Some(i), word.graphemes(true).count()));
+    }
+    let level = if rtl { Level::rtl() } else { Level::ltr() };
+    reorder(&shaped.join(" "), Some(level))
+        .into_iter()
+        .map(|(i, grapheme)| (owners.get(i).copied().flatten(), grapheme))
+        .collect()
+}
+
+/// Grapheme clusters of already shaped text in display order, with their
+/// logical indices. `level` is the paragraph direction, detected when `None`.
+fn reorder(shaped: &str, level: Option<Level>) -> Vec<(usize, String)> {
+    let graphemes: Vec<(usize, &str)> = shaped.grapheme_indices(true).collect();
+    if graphemes.is_empty() {
+        return Vec::new();
+    }
+    let info = ParagraphBidiInfo::new(shaped, level);
+    let (levels, runs) = info.visual_runs(0..shaped.len());
+    let mut visual = Vec::with_capacity(graphemes.len());
+    for run in runs {
+        let rtl = levels[run.start].is_rtl();
+        let mut indices: Vec<usize> = (0..graphemes.len())
+            .filter(|&i| run.contains(&graphemes[i].0))
+            .collect();
+        if rtl {
+            indices.reverse();
+        }
+        visual.extend(indices.into_iter().map(|i| {
+            let grapheme = graphemes[i].1;
+            (
+                i,
+                if rtl {
+                    mirror(grapheme)
+                } else {
+                    grapheme.to_string()
+                },
+            )
+        }));
+    }
+    visual
+}
+
+/// Replaces Arabic letters with the presentation form for their position in
+/// the word, joining lam-alef pairs. Text without Arabic is borrowed as is.
+fn shape(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| forms(c).is_some() || c == TATWEEL) {
+        return Cow::Borrowed(text);
+    }
+    let chars: Vec<char> = text.chars().collect();
+    // Neighbouring letters, skipping vowel marks, which don't break a join
+    let letter_before = |i: usize| {
+        chars[..i]
+            .iter()
+            .rev()
+            .copied()
+            .find(|&c| !is_transparent(c))
+    };
+    let index_after = |i: usize| (i + 1..chars.len()).find(|&j| !is_transparent(chars[j]));
+    let letter_after = |i: usize| index_after(i).map(|j| chars[j]);
+    let joins_forward =
+        |c: Option<char>| c.is_some_and(|c| c == TATWEEL || forms(c).is_some_and(|(_, n)| n == 4));
+    let joins_backward =
+        |c: Option<char>| c.is_some_and(|c| c == TATWEEL || forms(c).is_some_and(|(_, n)| n > 1));
+
+    let mut shaped = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let joined_before = joins_forward(letter_before(i));
+        // Vowel marks between the lam and alef move after the ligature
+        let ligature = (c == LAM).then(|| index_after(i)).flatten().and_then(|j| {
+            LAM_ALEF
+                .iter()
+                .find(|(alef, _)| *alef == chars[j])
+                .map(|&(_, isolated)| (j, isolated))
+        });
+        if let Some((alef, isolated)) = ligature {
+            shaped.push(offset(isolated, joined_before as u32));
+            shaped.extend(&chars[i + 1..alef]);
+            i = alef + 1;
+            continue;
+        }
+        match forms(c) {
+            Some((isolated, count)) if count > 1 => {
+                let joined_after = count == 4 && joins_backward(letter_after(i));
+                let form = match (joined_before, joined_after) {
+                    (false, false) => 0,
+                    (true, false) => 1,
+                    (false, true) => 2,
+                    (true, true) => 3,
+                };
+                shaped.push(offset(isolated, form));
+            }
+            Some((isolated, _)) => shaped.push(isolated),
+            None => shaped.push(c),
+        }
+        i += 1;
+    }
+    Cow::Owned(shaped)
+}
+
+fn forms(c: char) -> Option<(char, u8)> {
+    ARABIC_FORMS
+        .iter()
+        .find(|(letter, _, _)| *letter == c)
+        .map(|&(_, isolated, count)| (isolated, count))
+}
+
+/// Vowel marks and other combining Arabic signs.
+fn is_transparent(c: char) -> bool {
+    matches!(c, '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}')
+}
+
+/// Swaps opening and closing brackets, which point the other way in
+/// right-to-left text.
+fn mirror(grapheme: &str) -> String {
+    grapheme
+        .chars()
+        .map(|c| match c {
+            '(' => ')',
+            ')' => '(',
+            '[' => ']',
+            ']' => '[',
+            '{' => '}',
+            '}' => '{',
+            '<' => '>',
+            '>' => '<',
+            '«' => '»',
+            '»' => '«',
+            c => c,
+        })
+        .collect()
+}
+
+fn offset(c: char, by: u32) -> char {
+    char::from_u32(c as u32 + by).unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(text: &str) -> Vec<u32> {
+        text.chars().map(|c| c as u32).collect()
+    }
+
+    #[test]
+    fn letters_take_their_joined_forms() {
+        // مرحبا: initial meem, final reh, initial hah, medial beh, final alef
+        assert_eq!(codes(&shape("مرحبا")), [0xFEE3, 0xFEAE, 0xFEA3, 0xFE92, 0xFE8E]);
+        // A vowel mark doesn't break the join: بَب is initial beh, mark, final beh
+        assert_eq!(codes(&shape("بَب")), [0xFE91, 0x064E, 0xFE90]);
+        assert_eq!(codes(&shape("ء")), [0xFE80]);
+    }
+
+    #[test]
+    fn lam_alef_becomes_a_ligature() {
+        assert_eq!(codes(&shape("لا")), [0xFEFB]);
+        // السلام: the lam-alef joins the seen before it
+        assert_eq!(codes(&shape("السلام")), [0xFE8D, 0xFEDF, 0xFEB4, 0xFEFC, 0xFEE1]);
+        // With a vowel mark on the lam, the mark follows the ligature
+        assert_eq!(codes(&shape("لَا")), [0xFEFB, 0x064E]);
+        assert_eq!(codes(&shape("سلَام")), [0xFEB3, 0xFEFC, 0x064E, 0xFEE1]);
+    }
+
+    #[test]
+    fn right_to_left_text_is_reversed_and_mirrored() {
+        assert_eq!(visual("שלום"), "םולש");
+        assert_eq!(visual("(שלום)"), "(םולש)");
+        assert_eq!(visual("hello"), "hello");
+    }
+
+    #[test]
+    fn lines_keep_embedded_runs_in_order() {
+        let words = ["שלום", "Windows", "11", "עולם"];
+        let line: String = visual_line(&words, true)
+            .into_iter()
+            .map(|(_, grapheme)| grapheme)
+            .collect();
+        assert_eq!(line, "םלוע Windows 11 םולש");
+        let owners: Vec<Option<usize>> = visual_line(&["אב", "x"], true)
+            .into_iter()
+            .map(|(owner, _)| owner)
+            .collect();
+        assert_eq!(owners, [Some(1), None, Some(0), Some(0)]);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::dirs;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// A named position in a document, set with `m{a-z}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub word: usize,
+    /// A few words starting at the mark, for listing.
+    pub excerpt: String,
+}
+
+/// All marks set in one document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentBookmarks {
+    /// Last path the document was opened from, for display only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Words in the document when the marks were saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_words: Option<usize>,
+    /// Marks keyed by their letter.
+    #[serde(default)]
+    pub marks: BTreeMap<String, Bookmark>,
+}
+
+impl DocumentBookmarks {
+    /// Whether the marks were set in a document of `total_words` words, as
+    /// for `SavedPosition::applies_to`. Marks saved without a count are
+    /// taken as they are.
+    pub fn applies_to(&self, total_words: usize) -> bool {
+        self.total_words.is_none_or(|saved| saved == total_words)
+    }
+}
+
+/// Bookmarks for every document, keyed like saved positions by a hash of
+/// the document content (see `position::document_key`).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BookmarkStore {
+    #[serde(default)]
+    documents: BTreeMap<String, DocumentBookmarks>,
+}
+
+impl BookmarkStore {
+    pub fn load() -> Result<Self> {
+        // Without a data directory there are no bookmarks to load
+        let Ok(path) = Self::store_path() else {
+            return Ok(Self::default());
+        };
+        let path = dirs::readable(path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| dirs::with_path(&path, e))?;
+        toml::from_str(&contents).map_err(|e| {
+            dirs::with_path(&path, io::Error::new(io::ErrorKind::InvalidData, e)).into()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        dirs::write(&Self::store_path()?, &toml::to_string_pretty(self)?)
+    }
+
+    pub fn store_path() -> Result<PathBuf> {
+        Ok(dirs::data_dir()?.join("bookmarks.toml"))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&DocumentBookmarks> {
+        self.documents.get(key)
+    }
+
+    /// Replaces a document's bookmarks, dropping the entry when it has none.
+    pub fn set(&mut self, key: &str, bookmarks: DocumentBookmarks) {
+        if bookmarks.marks.is_empty() {
+            self.documents.remove(key);
+        } else {
+            self.documents.insert(key.to_string(), bookmarks);
+        }
+    }
+}
+
+/// Prints a document's marks, one per line, for `spr bookmarks <file>`.
+pub fn print_list(bookmarks: Option<&DocumentBookmarks>, total_words: usize) {
+    let Some(bookmarks) = bookmarks.filter(|b| !b.marks.is_empty()) else {
+        println!("No bookmarks");
+        return;
+    };
+    if let Some(saved) = bookmarks.total_words.filter(|_| !bookmarks.applies_to(total_words)) {
+        println!(
+            "Bookmarks were set when the document had {} words, not {} (was `lang` changed?)",
+            saved, total_words
+        );
+        return;
+    }
+    for (name, mark) in &bookmarks.marks {
+        let percent = (mark.word + 1) * 100 / total_words.max(1);
+        println!(
+            "{}  word {:>7}/{} ({:>3}%)  {}",
+            name,
+            mark.word + 1,
+            total_words,
+            percent,
+            mark.excerpt
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_only_apply_at_the_word_count_they_were_set_at() {
+        let mut bookmarks = DocumentBookmarks::default();
+        assert!(bookmarks.applies_to(10));
+        bookmarks.total_words = Some(10);
+        assert!(bookmarks.applies_to(10));
+        assert!(!bookmarks.applies_to(14));
+    }
+}
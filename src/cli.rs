@@ -1,27 +1,50 @@
-use clap::Parser;
+use crate::{
+    config::{self, Config, Override},
+    document::{Document, Format},
+    state::{MAX_WPM, MIN_WPM},
+};
+use clap::{Parser, Subcommand};
 use color_eyre::Result;
-use std::{fs, io};
+use std::{
+    io::{self, BufRead, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Text to read
     #[arg(short, long, conflicts_with = "file")]
     pub text: Option<String>,
 
-    /// File to read text from
+    /// File to read text from (`-` reads from stdin)
     #[arg(short, long)]
     pub file: Option<String>,
 
+    /// Source format (guessed from the file extension when omitted)
+    #[arg(long, value_enum, global = true)]
+    pub format: Option<Format>,
+
     /// Words per minute
-    #[arg(long, default_value = "300")]
+    #[arg(
+        long,
+        default_value = "300",
+        value_parser = clap::value_parser!(u64).range(MIN_WPM..=MAX_WPM)
+    )]
     pub wpm: u64,
 
     /// Number of upcoming words to preview below the current word
     #[arg(short = 'p', long)]
     pub preview_words: Option<usize>,
 
-    /// Inline mode (defaults to config value if not specified)
+    /// Words shown together per flash, grouped into short phrases
+    #[arg(long, value_name = "N")]
+    pub chunk: Option<usize>,
+
+    /// Inline mode (overrides the `inline` config key)
     #[arg(
         short,
         long,
@@ -31,16 +54,244 @@ pub struct Args {
         action = clap::ArgAction::Set
     )]
     pub inline: Option<bool>,
+
+    /// Start from the beginning, ignoring any saved position
+    #[arg(long, conflicts_with = "resume")]
+    pub restart: bool,
+
+    /// Resume from the saved position without asking
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Start at the given chapter (1-based) of a document with chapters
+    #[arg(long, conflicts_with_all = ["resume", "restart"])]
+    pub chapter: Option<usize>,
+
+    /// Config file to use instead of the default one
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Override any config key, e.g. `--set timing.comma=1.2` (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_set, global = true)]
+    pub set: Vec<(String, String)>,
+
+    /// Color theme: a built-in (default, solarized, gruvbox, high-contrast,
+    /// monochrome) or a file in the themes directory
+    #[arg(long, value_name = "NAME")]
+    pub theme: Option<String>,
+
+    /// Terminal color support: auto, truecolor, 256, 16 or none
+    #[arg(long, value_name = "MODE")]
+    pub color_mode: Option<String>,
+
+    /// Border color (name, `#rrggbb` or `r,g,b`)
+    #[arg(long, value_name = "COLOR")]
+    pub border_color: Option<String>,
+
+    /// Progress bar color (name, `#rrggbb` or `r,g,b`)
+    #[arg(long, value_name = "COLOR")]
+    pub progress_bar_color: Option<String>,
+
+    /// Focus letter color (name, `#rrggbb` or `r,g,b`)
+    #[arg(long, value_name = "COLOR")]
+    pub focus_color: Option<String>,
+
+    /// Draw the border in inline mode
+    #[arg(long, default_missing_value = "true", num_args = 0..=1, action = clap::ArgAction::Set)]
+    pub show_border: Option<bool>,
+
+    /// Show the progress bar
+    #[arg(long, default_missing_value = "true", num_args = 0..=1, action = clap::ArgAction::Set)]
+    pub show_progress_bar: Option<bool>,
+
+    /// Play the border animation on startup
+    #[arg(long, default_missing_value = "true", num_args = 0..=1, action = clap::ArgAction::Set)]
+    pub enable_animations: Option<bool>,
+
+    /// Words skipped by fast-forward and rewind
+    #[arg(long, value_name = "WORDS")]
+    pub seek_step: Option<usize>,
+
+    /// WPM change per speed up / slow down key press
+    #[arg(long, value_name = "WPM")]
+    pub wpm_step: Option<u64>,
+
+    /// Leave code blocks out when reading Markdown
+    #[arg(long, default_missing_value = "true", num_args = 0..=1, action = clap::ArgAction::Set)]
+    pub skip_code_blocks: Option<bool>,
+
+    /// Show words wider than this many columns in parts (0 = never split)
+    #[arg(long, value_name = "COLUMNS")]
+    pub max_word_width: Option<usize>,
+
+    /// Language of the text: auto, none, or a code like zh, ja or th
+    #[arg(long, value_name = "LANG")]
+    pub lang: Option<String>,
+}
+
+impl Args {
+    /// Config overrides given on the command line: `--set` pairs first, then
+    /// the dedicated flags, so a flag wins over `--set` for the same key.
+    pub fn config_overrides(&self) -> Vec<Override> {
+        let mut overrides: Vec<Override> = self
+            .set
+            .iter()
+            .map(|(key, value)| (key.clone(), config::parse_value(value)))
+            .collect();
+
+        let strings = [
+            ("theme", &self.theme),
+            ("color_mode", &self.color_mode),
+            ("border_color", &self.border_color),
+            ("progress_bar_color", &self.progress_bar_color),
+            ("focus_color", &self.focus_color),
+            ("lang", &self.lang),
+        ];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                overrides.push((key.to_string(), toml::Value::String(value.clone())));
+            }
+        }
+
+        let bools = [
+            ("inline", self.inline),
+            ("show_border", self.show_border),
+            ("show_progress_bar", self.show_progress_bar),
+            ("enable_animations", self.enable_animations),
+            ("skip_code_blocks", self.skip_code_blocks),
+        ];
+        for (key, value) in bools {
+            if let Some(value) = value {
+                overrides.push((key.to_string(), toml::Value::Boolean(value)));
+            }
+        }
+
+        let numbers = [
+            ("preview_words", self.preview_words.map(|n| n as u64)),
+            ("chunk", self.chunk.map(|n| n as u64)),
+            ("seek_step", self.seek_step.map(|n| n as u64)),
+            ("max_word_width", self.max_word_width.map(|n| n as u64)),
+            ("wpm_step", self.wpm_step),
+        ];
+        for (key, value) in numbers {
+            if let Some(value) = value {
+                overrides.push((key.to_string(), toml::Value::Integer(value as i64)));
+            }
+        }
+
+        overrides
+    }
 }
 
-pub fn get_content(args: &Args) -> Result<String> {
+/// Splits a `--set` argument into its key and raw value.
+fn parse_set(text: &str) -> Result<(String, String), String> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{}`", text))?;
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List the bookmarks saved for a document
+    Bookmarks {
+        /// Document to list bookmarks for
+        file: String,
+    },
+    /// Summarize reading history: totals, speed over time and progress per document
+    Stats,
+    /// Create or check the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Write a commented config file with the default settings
+    Init {
+        /// Replace an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Report invalid colors, out-of-range numbers and unknown keys
+    Check,
+}
+
+pub fn get_content(args: &Args, config: &Config) -> Result<Document> {
     match (&args.file, &args.text) {
-        (Some(file), None) => Ok(fs::read_to_string(file)?),
-        (None, Some(text)) => Ok(text.clone()),
+        (Some(file), None) if file == "-" => from_stdin(args, config),
+        (Some(file), None) => Document::from_file(Path::new(file), args.format, config),
+        (None, Some(text)) => {
+            let format = args.format.unwrap_or(Format::Text);
+            Document::from_source(text.clone(), format, config)
+        }
+        // Nothing given on the command line: fall back to piped input
+        (None, None) if !io::stdin().is_terminal() => from_stdin(args, config),
         _ => Err(io::Error::new(
             io::ErrorKind::InvalidInput,
-            "Either --text or --file must be provided",
+            "Either --text or --file must be provided, or text piped on stdin",
         )
         .into()),
     }
 }
+
+/// Reads a document from stdin, sniffing the format unless `--format` is set.
+fn from_stdin(args: &Args, config: &Config) -> Result<Document> {
+    let source = read_stdin()?;
+    let format = args.format.unwrap_or_else(|| Format::sniff(&source));
+    Document::from_source(source, format, config)
+}
+
+/// Reads all of stdin to a string. Keyboard input is unaffected because
+/// crossterm reads key events from `/dev/tty` when stdin is not a terminal.
+fn read_stdin() -> Result<String> {
+    let mut content = String::new();
+    io::stdin().read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Asks whether to continue from a saved position. An empty answer means yes;
+/// without a terminal to ask on, the saved position is used.
+pub fn confirm_resume(word: usize, total_words: usize) -> Result<bool> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(true);
+    }
+    eprint!("Resume at word {}/{}? [Y/n] ", word + 1, total_words);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+    Ok(!matches!(answer.trim().to_lowercase().as_str(), "n" | "no"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_come_after_set_so_they_win() {
+        let args = Args::parse_from([
+            "spr",
+            "--set",
+            "chunk=2",
+            "--chunk",
+            "3",
+            "--set",
+            "timing.comma = 1.2",
+            "--inline=false",
+        ]);
+        let overrides = args.config_overrides();
+        let keys: Vec<&str> = overrides.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["chunk", "timing.comma", "inline", "chunk"]);
+        assert_eq!(overrides[1].1, toml::Value::Float(1.2));
+        assert_eq!(overrides[2].1, toml::Value::Boolean(false));
+        assert_eq!(overrides[3].1, toml::Value::Integer(3));
+    }
+
+    #[test]
+    fn set_needs_a_key_and_value() {
+        assert!(Args::try_parse_from(["spr", "--set", "chunk"]).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use ratatui::{buffer::Buffer, style::Color};
+use std::env;
+
+/// Cube levels of the xterm 256-color palette (indices 16-231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The 16 ANSI colors with xterm's default RGB values.
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    /// No color at all; emphasis comes from bold, underline and dim.
+    NoColor,
+}
+
+impl ColorDepth {
+    /// Values accepted by the `color_mode` config key.
+    pub const MODES: [&'static str; 5] = ["auto", "truecolor", "256", "16", "none"];
+
+    /// Resolves a `color_mode` value, detecting the depth for `auto`.
+    /// `None` for an unknown mode.
+    pub fn from_mode(mode: &str) -> Option<Self> {
+        match mode {
+            "auto" => Some(Self::detect()),
+            "truecolor" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            "none" => Some(ColorDepth::NoColor),
+            _ => None,
+        }
+    }
+
+    /// `NO_COLOR` turns color off; otherwise `COLORTERM` and `TERM` decide.
+    pub fn detect() -> Self {
+        Self::from_env(|name| env::var(name).unwrap_or_default())
+    }
+
+    /// [`detect`](Self::detect) with environment variables looked up by `env`.
+    fn from_env(env: impl Fn(&str) -> String) -> Self {
+        let var = |name| env(name).to_lowercase();
+        if !var("NO_COLOR").is_empty() {
+            return ColorDepth::NoColor;
+        }
+        if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            return ColorDepth::TrueColor;
+        }
+        let term = var("TERM");
+        if term == "dumb" {
+            ColorDepth::NoColor
+        } else if term.ends_with("-direct") || term.contains("truecolor") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else if !var("WT_SESSION").is_empty() {
+            // Windows Terminal supports 24-bit color but sets neither variable
+            ColorDepth::TrueColor
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// The closest color this depth can show.
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (_, Color::Reset) | (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::NoColor, _) => Color::Reset,
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256((r, g, b))),
+            (ColorDepth::Ansi256, _) => color,
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_16((r, g, b)),
+            (ColorDepth::Ansi16, Color::Indexed(index)) if index >= 16 => {
+                nearest_16(indexed_rgb(index))
+            }
+            (ColorDepth::Ansi16, Color::Indexed(index)) => ANSI_16[index as usize].0,
+            (ColorDepth::Ansi16, _) => color,
+        }
+    }
+
+    /// Rewrites every cell's colors to ones the terminal can show. Run last
+    /// in a draw so computed colors and effects are covered too.
+    pub fn apply(self, buffer: &mut Buffer) {
+        if self == ColorDepth::TrueColor {
+            return;
+        }
+        for cell in &mut buffer.content {
+            cell.fg = self.adapt(cell.fg);
+            cell.bg = self.adapt(cell.bg);
+        }
+    }
+}
+
+/// Squared distance weighted for the eye's sensitivity to green.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    2 * d(a.0, b.0) + 4 * d(a.1, b.1) + 3 * d(a.2, b.2)
+}
+
+/// Nearest entry of the 6x6x6 cube or the grayscale ramp.
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    if distance(rgb, indexed_rgb(gray)) < distance(rgb, indexed_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, entry)| distance(rgb, *entry))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// RGB value of a 256-color palette index.
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let n = index - 16;
+            (
+                CUBE_LEVELS[(n / 36) as usize],
+                CUBE_LEVELS[(n / 6 % 6) as usize],
+                CUBE_LEVELS[(n % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(vars: &[(&str, &str)]) -> ColorDepth {
+        ColorDepth::from_env(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map_or(String::new(), |(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn no_color_wins_over_everything_else() {
+        let vars = [("NO_COLOR", "1"), ("COLORTERM", "truecolor"), ("TERM", "xterm-256color")];
+        assert_eq!(detect(&vars), ColorDepth::NoColor);
+        // An empty NO_COLOR doesn't count
+        assert_eq!(detect(&[("NO_COLOR", ""), ("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+    }
+
+    #[test]
+    fn depth_follows_colorterm_then_term() {
+        assert_eq!(detect(&[("COLORTERM", "24bit"), ("TERM", "xterm")]), ColorDepth::TrueColor);
+        assert_eq!(detect(&[("TERM", "xterm-direct")]), ColorDepth::TrueColor);
+        assert_eq!(detect(&[("TERM", "screen-256color")]), ColorDepth::Ansi256);
+        assert_eq!(detect(&[("TERM", "dumb")]), ColorDepth::NoColor);
+        assert_eq!(detect(&[("TERM", "xterm"), ("WT_SESSION", "x")]), ColorDepth::TrueColor);
+        assert_eq!(detect(&[("TERM", "xterm")]), ColorDepth::Ansi16);
+        assert_eq!(detect(&[]), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn colors_are_downsampled_to_the_nearest_available() {
+        let orange = Color::Rgb(255, 165, 0);
+        assert_eq!(ColorDepth::TrueColor.adapt(orange), orange);
+        assert_eq!(ColorDepth::Ansi256.adapt(orange), Color::Indexed(214));
+        assert_eq!(ColorDepth::Ansi256.adapt(Color::Rgb(128, 128, 128)), Color::Indexed(244));
+        assert_eq!(ColorDepth::Ansi16.adapt(orange), Color::Yellow);
+        assert_eq!(ColorDepth::Ansi16.adapt(Color::Indexed(196)), Color::LightRed);
+        assert_eq!(ColorDepth::Ansi16.adapt(Color::Indexed(4)), Color::Blue);
+        assert_eq!(ColorDepth::NoColor.adapt(Color::Cyan), Color::Reset);
+        assert_eq!(ColorDepth::Ansi16.adapt(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn explicit_modes_skip_detection() {
+        assert_eq!(ColorDepth::from_mode("none"), Some(ColorDepth::NoColor));
+        assert_eq!(ColorDepth::from_mode("256"), Some(ColorDepth::Ansi256));
+        assert_eq!(ColorDepth::from_mode("8"), None);
+    }
+}
@@ -1,18 +1,148 @@
+use crate::{
+    color::ColorDepth,
+    dirs,
+    keys::{Action, KeyBinding, Keymap},
+    segment,
+    state::MAX_WPM,
+    theme::{self, Palette, Theme},
+    timing::Timing,
+};
 use color_eyre::Result;
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Prefix of environment variables that override config keys, e.g.
+/// `SPR_SEEK_STEP=5` or `SPR_TIMING__SENTENCE_END=2.5`.
+const ENV_PREFIX: &str = "SPR_";
+
+/// Written by `spr config init`. Values are the defaults.
+const DEFAULT_CONFIG: &str = r##"# SPR-Reader configuration
+#
+# Every key is optional; the values below are the defaults. Any key can also be
+# overridden for one run with an SPR_<KEY> environment variable or
+# `--set key=value`. Run `spr config check` after editing.
+
+# Color theme: default, solarized, gruvbox, high-contrast, monochrome, or the
+# name of a file in the themes directory next to this one (without .toml).
+theme = "default"
+
+# Individual colors override the theme. Colors are a name ("cyan",
+# "lightcyan", "darkgray", "reset" for the terminal's own color, ...),
+# "#rrggbb" or "r,g,b".
+# border_color = "60,100,100"
+# progress_bar_color = "60,100,100"
+# Focus letter. Inherits border_color when unset.
+# focus_color = "#ff5555"
+# word_color = "reset"
+# Preview words and other secondary text.
+# preview_color = "darkgray"
+# Surrounding words shown while paused.
+# context_color = "gray"
+# Progress bar while paused.
+# paused_color = "255,165,0"
+# Key names in the help popup.
+# help_key_color = "yellow"
+
+# Terminal color support: "auto" (detect from NO_COLOR, COLORTERM and TERM),
+# "truecolor", "256", "16" or "none". Colors are downsampled to fit.
+color_mode = "auto"
+
+# Draw the border in inline mode.
+show_border = true
+show_progress_bar = true
+# Play the border animation on startup.
+enable_animations = true
+# true: compact 5-line view below the prompt. false: fullscreen.
+inline = true
+
+# Words skipped by fast-forward and rewind.
+seek_step = 10
+# Upcoming words previewed next to the current word (0 = off).
+preview_words = 0
+# Words shown together per flash. Phrases stop at punctuation and stay
+# within max_word_width.
+chunk = 1
+# WPM change per speed up / slow down key press.
+wpm_step = 25
+# Leave code blocks out when reading Markdown.
+skip_code_blocks = true
+# Words wider than this many columns are shown in parts, split at hyphens,
+# underscores, slashes or syllables (0 = never split, otherwise at least 2).
+# Also the widest a chunk of words can be.
+max_word_width = 20
+# Language of the text, used to split Chinese, Japanese and Thai into words:
+# "auto" (guess from the script), a language code like "zh", "ja" or "th",
+# or "none" to only split on spaces.
+lang = "auto"
+# Shape Arabic and put right-to-left words in display order. Turn off for
+# terminals that do this themselves.
+reorder_rtl = true
+
+# How long particular words stay on screen, as multiples of 60 / wpm seconds.
+[timing]
+# Words ending in , ; : or a dash.
+comma = 1.5
+# Words ending in . ! ? or an ellipsis.
+sentence_end = 2.0
+# The last word of a paragraph.
+paragraph_end = 2.5
+# Words longer than long_word_length characters.
+long_word = 1.3
+long_word_length = 8
+# Words containing a digit.
+number = 1.3
+# Speed warms up from ramp_start x wpm to full over ramp_words words after
+# starting, resuming and seeking (ramp_words = 0 disables).
+ramp_start = 0.5
+ramp_words = 5
+
+# Key overrides: action = "key" or ["key", ...]. The README lists the actions.
+[keys]
+# quit = ["q", "esc"]
+# pause = "space"
+"##;
+
+/// Where override problems are reported as coming from.
+const OVERRIDE_SOURCE: &str = "config override (SPR_* environment or command line)";
+
+/// A dotted config key and the value to put there, e.g. `timing.comma = 1.2`.
+pub type Override = (String, toml::Value);
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
-    #[serde(default = "default_border_color")]
-    pub border_color: String,
-    #[serde(default = "default_progress_bar_color")]
-    pub progress_bar_color: String,
+    /// Built-in or user theme. The `*_color` keys below override it.
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_bar_color: Option<String>,
     /// Focus letter color. When absent, inherits the border color.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub focus_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word_color: Option<String>,
+    /// Preview words and other secondary text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_color: Option<String>,
+    /// Surrounding words shown while paused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_color: Option<String>,
+    /// Progress bar color while paused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_color: Option<String>,
+    /// Key names in the help popup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help_key_color: Option<String>,
+    /// Terminal color support: `auto` detects it from `NO_COLOR`,
+    /// `COLORTERM` and `TERM`.
+    #[serde(default = "default_color_mode")]
+    pub color_mode: String,
     #[serde(default = "default_show_border")]
     pub show_border: bool,
     #[serde(default = "default_show_progress_bar")]
@@ -26,14 +156,39 @@ pub struct Config {
     /// Number of upcoming words to preview below the current word (0 = disabled).
     #[serde(default = "default_preview_words")]
     pub preview_words: usize,
+    /// Most words shown together per flash (1 = one at a time).
+    #[serde(default = "default_chunk")]
+    pub chunk: usize,
+    /// WPM change per `+` / `-` key press.
+    #[serde(default = "default_wpm_step")]
+    pub wpm_step: u64,
+    /// Leave code blocks out when reading Markdown.
+    #[serde(default = "default_skip_code_blocks")]
+    pub skip_code_blocks: bool,
+    /// Words wider than this many columns are shown in parts (0 = never).
+    #[serde(default = "default_max_word_width")]
+    pub max_word_width: usize,
+    /// Language of the text, deciding how text without spaces is split into
+    /// words. `auto` guesses from the script.
+    #[serde(default = "default_lang")]
+    pub lang: String,
+    /// Shape and reorder right-to-left words, for terminals that don't.
+    #[serde(default = "default_reorder_rtl")]
+    pub reorder_rtl: bool,
+    /// Per-word display time multipliers.
+    #[serde(default)]
+    pub timing: Timing,
+    /// Key overrides: action name -> one key chord or a list of them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, KeyBinding>,
 }
 
-fn default_border_color() -> String {
-    "60,100,100".to_string() // Muted cyan RGB
+fn default_theme() -> String {
+    "default".to_string()
 }
 
-fn default_progress_bar_color() -> String {
-    "60,100,100".to_string() // Muted cyan RGB to match border
+fn default_color_mode() -> String {
+    "auto".to_string()
 }
 
 fn default_show_border() -> bool {
@@ -60,135 +215,594 @@ fn default_preview_words() -> usize {
     0
 }
 
+fn default_chunk() -> usize {
+    1
+}
+
+fn default_wpm_step() -> u64 {
+    25
+}
+
+fn default_skip_code_blocks() -> bool {
+    true
+}
+
+fn default_max_word_width() -> usize {
+    20
+}
+
+fn default_lang() -> String {
+    "auto".to_string()
+}
+
+fn default_reorder_rtl() -> bool {
+    true
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
-            border_color: default_border_color(),
-            progress_bar_color: default_progress_bar_color(),
+            theme: default_theme(),
+            border_color: None,
+            progress_bar_color: None,
             focus_color: None,
+            word_color: None,
+            preview_color: None,
+            context_color: None,
+            paused_color: None,
+            help_key_color: None,
+            color_mode: default_color_mode(),
             show_border: default_show_border(),
             show_progress_bar: default_show_progress_bar(),
             enable_animations: default_enable_animations(),
             inline: default_inline(),
             seek_step: default_seek_step(),
             preview_words: default_preview_words(),
+            chunk: default_chunk(),
+            wpm_step: default_wpm_step(),
+            skip_code_blocks: default_skip_code_blocks(),
+            max_word_width: default_max_word_width(),
+            lang: default_lang(),
+            reorder_rtl: default_reorder_rtl(),
+            timing: Timing::default(),
+            keys: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
-    pub fn load() -> Result<Self> {
-        let config_path = Self::config_path()?;
+    /// Loads the config file (`path`, or the default location if a file is
+    /// there), then applies `SPR_*` environment variables and finally
+    /// `overrides` from the command line, each layer winning over the one
+    /// before. Nothing is written. Fails listing every problem found, located
+    /// by line and column where possible.
+    pub fn load(path: Option<&Path>, overrides: &[Override]) -> Result<Self> {
+        let path = Self::file_to_read(path);
+        let env = env_overrides(std::env::vars());
+        Self::layered(path.as_deref(), &env, overrides).map_err(|problems| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, problems.join("\n")).into()
+        })
+    }
 
-        if !config_path.exists() {
-            // Create default config
-            let config = Self::default();
-            config.save()?;
-            return Ok(config);
-        }
+    /// Checks the config without starting the reader: the file that would be
+    /// read, if any, and every problem in it or in the overrides.
+    pub fn check(path: Option<&Path>, overrides: &[Override]) -> (Option<PathBuf>, Vec<String>) {
+        let path = Self::file_to_read(path);
+        let env = env_overrides(std::env::vars());
+        let problems = Self::layered(path.as_deref(), &env, overrides).err().unwrap_or_default();
+        (path, problems)
+    }
 
-        let contents = fs::read_to_string(&config_path)?;
-        let config: Config = toml::from_str(&contents)?;
-        Ok(config)
+    /// Writes the commented default config to `path` or the default location,
+    /// refusing to replace an existing file unless `force` is set.
+    pub fn init(path: Option<&Path>, force: bool) -> Result<PathBuf> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => Self::config_path()?,
+        };
+        if path.exists() && !force {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists (use --force to overwrite)", path.display()),
+            )
+            .into());
+        }
+        dirs::write(&path, DEFAULT_CONFIG)?;
+        Ok(path)
     }
 
-    pub fn save(&self) -> Result<()> {
-        let config_path = Self::config_path()?;
+    /// An explicit `path` is always read, so a missing file is an error;
+    /// without one, defaults apply until a config file is created.
+    fn file_to_read(path: Option<&Path>) -> Option<PathBuf> {
+        match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => Self::config_path()
+                .ok()
+                .map(dirs::readable)
+                .filter(|path| path.exists()),
+        }
+    }
 
-        // Ensure directory exists
-        if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent)?;
+    /// The file at `path`, then `env`, then `overrides`.
+    fn layered(
+        path: Option<&Path>,
+        env: &[Override],
+        overrides: &[Override],
+    ) -> std::result::Result<Self, Vec<String>> {
+        let mut table = match path {
+            Some(path) => {
+                let contents = fs::read_to_string(path)
+                    .map_err(|e| vec![format!("{}: {}", path.display(), e)])?;
+                let problems = check_source(path, &contents);
+                if !problems.is_empty() {
+                    return Err(problems);
+                }
+                toml::from_str(&contents).map_err(|e| vec![format!("{}: {}", path.display(), e)])?
+            }
+            None => toml::Table::new(),
+        };
+        for (key, value) in env.iter().chain(overrides) {
+            set_key(&mut table, key, value.clone())
+                .map_err(|e| vec![format!("{}: {}", OVERRIDE_SOURCE, e)])?;
         }
 
-        let contents = toml::to_string_pretty(self)?;
-        fs::write(&config_path, contents)?;
-        Ok(())
+        let config: Config = toml::Value::Table(table).try_into().map_err(|e: toml::de::Error| {
+            vec![format!("{}: {}", OVERRIDE_SOURCE, one_line(&e.to_string()))]
+        })?;
+        let problems: Vec<String> = config
+            .problems()
+            .into_iter()
+            .map(|(_, message)| format!("{}: {}", OVERRIDE_SOURCE, message))
+            .collect();
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(problems)
+        }
     }
 
-    pub fn config_dir() -> Result<PathBuf> {
-        let home = std::env::var("HOME")
-            .map_err(|_| std::io::Error::new(std::io::ErrorKind::NotFound, "HOME not set"))?;
-        Ok(PathBuf::from(home).join(".config").join("SPR-Reader"))
+    /// Values that parse but are unusable, as `(dotted key, message)` pairs.
+    fn problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+
+        if let Err(message) = theme::load(&self.theme) {
+            problems.push(("theme".to_string(), message));
+        }
+        for (key, value) in self.colors().entries() {
+            if parse_color(value).is_none() {
+                problems.push((key.to_string(), invalid_color(key, value)));
+            }
+        }
+
+        if ColorDepth::from_mode(&self.color_mode).is_none() {
+            problems.push((
+                "color_mode".to_string(),
+                format!(
+                    "`color_mode` must be one of {}, got `{}`",
+                    ColorDepth::MODES.join(", "),
+                    self.color_mode
+                ),
+            ));
+        }
+
+        if self.seek_step == 0 {
+            problems.push(("seek_step".to_string(), "`seek_step` must be at least 1".to_string()));
+        }
+        if self.chunk == 0 {
+            problems.push(("chunk".to_string(), "`chunk` must be at least 1".to_string()));
+        }
+        // One column is taken by the continuation mark, so a part needs a second
+        if self.max_word_width == 1 {
+            problems.push((
+                "max_word_width".to_string(),
+                "`max_word_width` must be 0 (never split) or at least 2, got 1".to_string(),
+            ));
+        }
+        if self.wpm_step == 0 || self.wpm_step > MAX_WPM {
+            problems.push((
+                "wpm_step".to_string(),
+                format!("`wpm_step` must be between 1 and {}, got {}", MAX_WPM, self.wpm_step),
+            ));
+        }
+        if !segment::is_valid_language(&self.lang) {
+            problems.push((
+                "lang".to_string(),
+                format!(
+                    "`lang` must be `auto`, `none` or a language code like `zh` or `ja`, got `{}`",
+                    self.lang
+                ),
+            ));
+        }
+        problems.extend(self.timing.problems());
+        if let Err(e) = self.keymap() {
+            problems.push(("keys".to_string(), e.to_string()));
+        }
+
+        problems
     }
 
     pub fn config_path() -> Result<PathBuf> {
-        Ok(Self::config_dir()?.join("config.toml"))
+        Ok(dirs::config_dir()?.join("config.toml"))
     }
 
     pub fn first_use_marker_path() -> Result<PathBuf> {
-        Ok(Self::config_dir()?.join(".first_use_complete"))
+        Ok(dirs::state_dir()?.join(".first_use_complete"))
     }
 
-    pub fn is_first_use() -> Result<bool> {
-        let marker_path = Self::first_use_marker_path()?;
-        Ok(!marker_path.exists())
+    pub fn is_first_use() -> bool {
+        Self::first_use_marker_path()
+            .map(|path| !dirs::readable(path).exists())
+            .unwrap_or(false)
     }
 
     pub fn mark_first_use_complete() -> Result<()> {
-        let marker_path = Self::first_use_marker_path()?;
-
-        // Ensure directory exists
-        if let Some(parent) = marker_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+        dirs::write(&Self::first_use_marker_path()?, "")
+    }
 
-        fs::write(&marker_path, "")?;
-        Ok(())
+    /// Default key bindings with the `[keys]` overrides applied.
+    pub fn keymap(&self) -> Result<Keymap> {
+        Ok(Keymap::from_config(&self.keys)?)
     }
 
-    pub fn parse_border_color(&self) -> Color {
-        Self::parse_color_string(&self.border_color)
+    /// The `*_color` keys set in config, as a palette layer.
+    fn colors(&self) -> Palette {
+        Palette {
+            border_color: self.border_color.clone(),
+            progress_bar_color: self.progress_bar_color.clone(),
+            focus_color: self.focus_color.clone(),
+            word_color: self.word_color.clone(),
+            preview_color: self.preview_color.clone(),
+            context_color: self.context_color.clone(),
+            paused_color: self.paused_color.clone(),
+            help_key_color: self.help_key_color.clone(),
+        }
     }
 
-    pub fn parse_progress_bar_color(&self) -> Color {
-        Self::parse_color_string(&self.progress_bar_color)
+    /// UI colors: the default theme, then the selected theme, then any
+    /// `*_color` keys.
+    pub fn resolve_theme(&self) -> Result<Theme> {
+        let invalid = |message| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let mut palette = theme::load("default").map_err(invalid)?;
+        palette.overlay(&theme::load(&self.theme).map_err(invalid)?);
+        palette.overlay(&self.colors());
+        let depth = ColorDepth::from_mode(&self.color_mode).unwrap_or_else(ColorDepth::detect);
+        Ok(Theme::from_palette(&palette, depth))
     }
+}
 
-    /// Returns the focus letter color. Falls back to `border_color` when unset.
-    pub fn parse_focus_color(&self) -> Color {
-        match &self.focus_color {
-            Some(s) if !s.is_empty() => Self::parse_color_string(s),
-            _ => self.parse_border_color(),
+/// Parses a color name, `#rrggbb` or `r,g,b`. `None` for anything else.
+/// `reset` (or `default`) is the terminal's own color.
+pub fn parse_color(color_str: &str) -> Option<Color> {
+    let color = match color_str.trim().to_lowercase().as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" => Color::Gray,
+        "darkgray" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        s if s.starts_with('#') => {
+            let hex = &s[1..];
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            Color::Rgb(channel(0)?, channel(2)?, channel(4)?)
+        }
+        s if s.contains(',') => {
+            let channels = s
+                .split(',')
+                .map(|part| part.trim().parse::<u8>().ok())
+                .collect::<Option<Vec<_>>>()?;
+            match channels[..] {
+                [r, g, b] => Color::Rgb(r, g, b),
+                _ => return None,
+            }
         }
+        _ => return None,
+    };
+    Some(color)
+}
+
+pub fn invalid_color(key: &str, value: &str) -> String {
+    format!(
+        "`{}` is not a valid color: `{}` (use a name, `#rrggbb` or `r,g,b` with 0-255 channels)",
+        key, value
+    )
+}
+
+/// Every problem in a config file's contents, each prefixed with
+/// `path:line:column`. Unknown keys are reported and then blanked out, so
+/// they don't hide problems in the rest of the file.
+fn check_source(path: &Path, contents: &str) -> Vec<String> {
+    let document = match toml_edit::ImDocument::parse(contents) {
+        Ok(document) => document,
+        Err(e) => return vec![locate(path, contents, e.span(), e.message())],
+    };
+
+    let mut problems: Vec<(Option<Range<usize>>, String)> = Vec::new();
+    let mut known = contents.to_string();
+    let unknown = unknown_keys(document.as_table());
+    for (key, span) in &unknown {
+        if let Some(span) = span {
+            blank(&mut known, span.clone());
+        }
+        problems.push((span.clone(), format!("unknown key `{}`", key)));
     }
 
-    fn parse_color_string(color_str: &str) -> Color {
-        match color_str.to_lowercase().as_str() {
-            "black" => Color::Black,
-            "red" => Color::Red,
-            "green" => Color::Green,
-            "yellow" => Color::Yellow,
-            "blue" => Color::Blue,
-            "magenta" => Color::Magenta,
-            "cyan" => Color::Cyan,
-            "gray" => Color::Gray,
-            "darkgray" => Color::DarkGray,
-            "lightred" => Color::LightRed,
-            "lightgreen" => Color::LightGreen,
-            "lightyellow" => Color::LightYellow,
-            "lightblue" => Color::LightBlue,
-            "lightmagenta" => Color::LightMagenta,
-            "lightcyan" => Color::LightCyan,
-            "white" => Color::White,
-            // Try to parse RGB format like "#ff0000" or "255,128,0"
-            s if s.starts_with('#') && s.len() == 7 => {
-                let r = u8::from_str_radix(&s[1..3], 16).unwrap_or(0);
-                let g = u8::from_str_radix(&s[3..5], 16).unwrap_or(0);
-                let b = u8::from_str_radix(&s[5..7], 16).unwrap_or(0);
-                Color::Rgb(r, g, b)
-            }
-            s if s.contains(',') => {
-                let parts: Vec<&str> = s.split(',').collect();
-                if parts.len() == 3 {
-                    let r = parts[0].trim().parse().unwrap_or(0);
-                    let g = parts[1].trim().parse().unwrap_or(0);
-                    let b = parts[2].trim().parse().unwrap_or(0);
-                    Color::Rgb(r, g, b)
-                } else {
-                    Color::Cyan // fallback
+    match toml::from_str::<Config>(&known) {
+        Ok(config) => problems.extend(config.problems().into_iter().map(|(key, message)| {
+            (key_span(document.as_table(), &key), message)
+        })),
+        // An unknown key that couldn't be blanked, already reported
+        Err(e) if !unknown.is_empty() && e.message().starts_with("unknown field") => {}
+        Err(e) => problems.push((e.span(), e.message().to_string())),
+    }
+
+    problems.sort_by_key(|(span, _)| span.as_ref().map(|span| span.start));
+    problems
+        .into_iter()
+        .map(|(span, message)| locate(path, contents, span, &message))
+        .collect()
+}
+
+/// Dotted names and spans of the keys in a config file that aren't config
+/// keys. `[keys]` is left to the keymap, which names the bad action.
+fn unknown_keys(table: &toml_edit::Table) -> Vec<(String, Option<Range<usize>>)> {
+    let mut unknown = Vec::new();
+    for (name, item) in table.iter() {
+        let nested = match (name, item.as_table()) {
+            ("timing", Some(timing)) => timing,
+            ("keys", _) => continue,
+            _ => {
+                if !is_known_key(name) {
+                    unknown.push((name.to_string(), entry_span(table, name)));
                 }
+                continue;
+            }
+        };
+        for (field, _) in nested.iter() {
+            let key = format!("{}.{}", name, field);
+            if !is_known_key(&key) {
+                unknown.push((key, entry_span(nested, field)));
             }
-            _ => Color::Cyan, // fallback to default
         }
     }
+    unknown
+}
+
+/// Byte span of a whole `key = value` entry, or of a table from its header
+/// to its last entry.
+fn entry_span(table: &toml_edit::Table, key: &str) -> Option<Range<usize>> {
+    let (key, item) = table.get_key_value(key)?;
+    let start = key.span()?.start;
+    let end = match item.span() {
+        Some(span) => span.end,
+        // Dotted keys such as `a.b = 1` have no span of their own
+        None => {
+            let dotted = item.as_table()?;
+            dotted
+                .iter()
+                .filter_map(|(key, _)| entry_span(dotted, key).map(|span| span.end))
+                .max()?
+        }
+    };
+    let start = item.span().map_or(start, |span| span.start.min(start));
+    Some(start..end)
+}
+
+/// Replaces `span` of `text` with spaces, keeping line breaks so that later
+/// positions still point at the same line and column.
+fn blank(text: &mut String, span: Range<usize>) {
+    let blanked: String = text[span.clone()]
+        .chars()
+        .map(|c| if c == '\n' { c } else { ' ' })
+        .collect();
+    text.replace_range(span, &blanked);
+}
+
+/// Byte span of the value at a dotted key such as `timing.comma`.
+pub fn key_span(table: &toml_edit::Table, key: &str) -> Option<Range<usize>> {
+    let mut parts = key.split('.');
+    let mut item = table.get(parts.next()?)?;
+    for part in parts {
+        item = item.as_table_like()?.get(part)?;
+    }
+    item.span()
+}
+
+/// Formats `message` as `path:line:column: message`, or `path: message`
+/// without a span.
+pub fn locate(path: &Path, contents: &str, span: Option<Range<usize>>, message: &str) -> String {
+    let message = one_line(message);
+    let Some(span) = span else {
+        return format!("{}: {}", path.display(), message);
+    };
+    let before = &contents[..span.start.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    format!("{}:{}:{}: {}", path.display(), line, column, message)
+}
+
+/// Joins a possibly multi-line message onto one line.
+fn one_line(message: &str) -> String {
+    message.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parses a value given on the command line or in the environment as a TOML
+/// literal (`5`, `true`, `["q", "esc"]`), falling back to a plain string so
+/// colors like `#ff0000` or `60,100,100` need no quoting.
+pub fn parse_value(text: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", text))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(text.to_string()))
+}
+
+/// `SPR_*` variables among `vars` as overrides. A double underscore
+/// separates nested keys. Variables that don't name a config key are left
+/// alone, as they may belong to something else.
+fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Vec<Override> {
+    let mut overrides: Vec<Override> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase().replace("__", ".");
+            is_known_key(&key).then(|| (key, parse_value(&value)))
+        })
+        .collect();
+    overrides.sort_by(|a, b| a.0.cmp(&b.0));
+    overrides
+}
+
+/// Whether a dotted key names a config value: a top-level key, a `timing`
+/// multiplier or a `keys` action.
+fn is_known_key(key: &str) -> bool {
+    let defaults = toml::Table::try_from(Config::default()).unwrap_or_default();
+    match key.split_once('.') {
+        None => defaults.contains_key(key) || Palette::KEYS.contains(&key),
+        Some(("timing", field)) => defaults
+            .get("timing")
+            .and_then(toml::Value::as_table)
+            .is_some_and(|timing| timing.contains_key(field)),
+        Some(("keys", action)) => Action::ALL.iter().any(|a| a.name() == action),
+        _ => false,
+    }
+}
+
+/// Sets a dotted key such as `timing.comma`, creating tables along the way.
+fn set_key(table: &mut toml::Table, key: &str, value: toml::Value) -> Result<(), String> {
+    let invalid = || format!("invalid config key `{}`", key);
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().filter(|part| !part.is_empty()).ok_or_else(invalid)?;
+
+    let mut table = table;
+    for part in parts {
+        table = table
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(invalid)?;
+    }
+    table.insert(last.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn env_overrides_take_only_config_keys() {
+        let env = env_overrides(vars(&[
+            ("SPR_SEEK_STEP", "5"),
+            ("SPR_TIMING__SENTENCE_END", "2.5"),
+            ("SPR_KEYS__QUIT", r#"["x", "esc"]"#),
+            ("SPR_FOCUS_COLOR", "#ff0000"),
+            ("SPR_VERSION", "1.2"),
+            ("SPR_TIMING__SPEED", "2"),
+            ("HOME", "/home/reader"),
+        ]));
+        let keys: Vec<&str> = env.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["focus_color", "keys.quit", "seek_step", "timing.sentence_end"]);
+        assert_eq!(env[0].1, toml::Value::String("#ff0000".to_string()));
+        assert_eq!(env[3].1, toml::Value::Float(2.5));
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let path = std::env::temp_dir().join(format!("spr-layers-{}.toml", std::process::id()));
+        fs::write(&path, "seek_step = 3\nchunk = 2\n[timing]\ncomma = 1.1\n").unwrap();
+        let env = env_overrides(vars(&[("SPR_CHUNK", "3"), ("SPR_TIMING__COMMA", "1.2")]));
+        let cli = [("chunk".to_string(), toml::Value::Integer(4))];
+        let config = Config::layered(Some(&path), &env, &cli);
+        fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.seek_step, 3);
+        assert_eq!(config.chunk, 4);
+        assert_eq!(config.timing.comma, 1.2);
+        // Untouched keys keep their defaults
+        assert_eq!(config.timing.sentence_end, Timing::default().sentence_end);
+    }
+
+    #[test]
+    fn override_problems_are_reported() {
+        let wrong_type = [("chunk".to_string(), parse_value("many"))];
+        let problems = Config::layered(None, &[], &wrong_type).unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with(OVERRIDE_SOURCE), "{}", problems[0]);
+
+        let not_a_table = [("timing.comma.x".to_string(), toml::Value::Integer(1))];
+        assert!(Config::layered(None, &[], &not_a_table).is_err());
+    }
+
+    #[test]
+    fn max_word_width_leaves_room_for_a_part() {
+        let width = |n: i64| [("max_word_width".to_string(), toml::Value::Integer(n))];
+        let problems = Config::layered(None, &[], &width(1)).unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("`max_word_width` must be 0"), "{}", problems[0]);
+        assert!(Config::layered(None, &[], &width(0)).is_ok());
+        assert!(Config::layered(None, &[], &width(2)).is_ok());
+    }
+
+    #[test]
+    fn values_parse_as_toml_or_else_as_strings() {
+        assert_eq!(parse_value("5"), toml::Value::Integer(5));
+        assert_eq!(parse_value("true"), toml::Value::Boolean(true));
+        assert_eq!(parse_value("60,100,100"), toml::Value::String("60,100,100".to_string()));
+        assert_eq!(
+            parse_value(r#"["q", "esc"]"#),
+            toml::Value::Array(vec!["q".into(), "esc".into()])
+        );
+    }
+
+    #[test]
+    fn check_reports_every_problem_with_its_location() {
+        let path = Path::new("config.toml");
+        let contents = "seek_step = 0\ncolour = \"red\"\nfocus_color = \"purple\"\n\n[timing]\ncomma = -1\nspeed = 2\nramp_words = 500\n";
+        assert_eq!(
+            check_source(path, contents),
+            [
+                "config.toml:1:13: `seek_step` must be at least 1",
+                "config.toml:2:1: unknown key `colour`",
+                "config.toml:3:15: `focus_color` is not a valid color: `purple` (use a name, `#rrggbb` or `r,g,b` with 0-255 channels)",
+                "config.toml:6:9: `timing.comma` must be greater than 0 and at most 10, got -1",
+                "config.toml:7:1: unknown key `timing.speed`",
+                "config.toml:8:14: `timing.ramp_words` must be at most 100, got 500",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_reports_a_syntax_error_alone() {
+        let problems = check_source(Path::new("config.toml"), "chunk = 2\nseek_step = \n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("config.toml:2:"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn wrong_types_are_located() {
+        let problems = check_source(Path::new("config.toml"), "inline = true\nchunk = \"two\"\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("config.toml:2:9:"), "{}", problems[0]);
+    }
 }
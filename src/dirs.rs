@@ -0,0 +1,73 @@
+use color_eyre::Result;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+const APP_DIR: &str = "SPR-Reader";
+
+/// `$XDG_CONFIG_HOME/SPR-Reader`, by default `~/.config/SPR-Reader`. Holds
+/// `config.toml`.
+pub fn config_dir() -> Result<PathBuf> {
+    base_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_STATE_HOME/SPR-Reader`, by default `~/.local/state/SPR-Reader`.
+/// Holds reading positions and the first-run marker.
+pub fn state_dir() -> Result<PathBuf> {
+    base_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// `$XDG_DATA_HOME/SPR-Reader`, by default `~/.local/share/SPR-Reader`.
+/// Holds bookmarks and reading history.
+pub fn data_dir() -> Result<PathBuf> {
+    base_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn base_dir(variable: &str, fallback: &str) -> Result<PathBuf> {
+    // The spec says relative paths are invalid and must be ignored
+    if let Some(dir) = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+    {
+        return Ok(dir.join(APP_DIR));
+    }
+    let home = env::var_os("HOME").filter(|home| !home.is_empty()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Neither {} nor HOME is set", variable),
+        )
+    })?;
+    Ok(PathBuf::from(home).join(fallback).join(APP_DIR))
+}
+
+/// The file to read for `path`: `path` itself, or the file of the same name
+/// in `~/.config/SPR-Reader`, where everything lived before the XDG split,
+/// if only that one exists. Writes always go to `path`.
+pub fn readable(path: PathBuf) -> PathBuf {
+    if path.exists() {
+        return path;
+    }
+    let legacy = env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .zip(path.file_name())
+        .map(|(home, name)| PathBuf::from(home).join(".config").join(APP_DIR).join(name));
+    match legacy {
+        Some(legacy) if legacy.exists() => legacy,
+        _ => path,
+    }
+}
+
+/// Writes `contents` to `path`, creating its directory first.
+pub fn write(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| with_path(path, e))?;
+    }
+    fs::write(path, contents).map_err(|e| with_path(path, e))?;
+    Ok(())
+}
+
+/// Prefixes an I/O error with the path it happened on.
+pub fn with_path(path: &Path, error: io::Error) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
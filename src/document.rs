@@ -0,0 +1,197 @@
+use crate::{config::Config, epub, html, markdown, position, segment};
+use clap::ValueEnum;
+use color_eyre::Result;
+use std::{fs, iter, path::Path};
+
+/// Source formats understood by the reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Markdown,
+    Html,
+    Epub,
+}
+
+impl Format {
+    /// Guesses the format of piped or inline text: HTML documents are
+    /// recognised by their opening tag, anything else is plain text.
+    pub fn sniff(source: &str) -> Self {
+        if html::looks_like_html(source) {
+            Format::Html
+        } else {
+            Format::Text
+        }
+    }
+
+    /// Guesses the format from a file extension, defaulting to plain text.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("epub") => Format::Epub,
+            Some("md" | "markdown") => Format::Markdown,
+            Some("html" | "htm" | "xhtml") => Format::Html,
+            _ => Format::Text,
+        }
+    }
+}
+
+/// A titled section of a document, starting at `start_word`.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub start_word: usize,
+}
+
+/// Inline styling carried over from the source markup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Emphasis {
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// Text ready for tokenizing, plus any structure recovered from the source
+/// format. Plain text has no chapters and no emphasis.
+#[derive(Debug, Default)]
+pub struct Document {
+    pub text: String,
+    pub chapters: Vec<Chapter>,
+    /// Per-word emphasis, indexed like the tokenized words. Empty when the
+    /// source carries no styling.
+    pub emphasis: Vec<Emphasis>,
+    /// Key of the text before `segmented` put spaces into it.
+    pub source_key: Option<String>,
+}
+
+impl Document {
+    pub fn from_text(text: String) -> Self {
+        Self {
+            text,
+            ..Self::default()
+        }
+    }
+
+    /// Parses in-memory source text in the given format.
+    pub fn from_source(source: String, format: Format, config: &Config) -> Result<Self> {
+        let document = match format {
+            Format::Text => Self::from_text(source),
+            Format::Markdown => markdown::parse(&source, config.skip_code_blocks),
+            Format::Html => Self::from_text(html::extract_article(&source)),
+            Format::Epub => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "EPUB can only be read from a file",
+                )
+                .into())
+            }
+        };
+        Ok(document.segmented(&config.lang))
+    }
+
+    /// Reads a file, using `format` or guessing it from the extension.
+    pub fn from_file(path: &Path, format: Option<Format>, config: &Config) -> Result<Self> {
+        match format.unwrap_or_else(|| Format::from_path(path)) {
+            Format::Epub => Ok(epub::load(path)?.segmented(&config.lang)),
+            format => Self::from_source(fs::read_to_string(path)?, format, config),
+        }
+    }
+
+    /// Appends a chapter, separating it from the previous one by a blank
+    /// line so it starts a new paragraph. Chapters without words are dropped.
+    pub fn push_chapter(&mut self, title: String, text: &str) {
+        if text.split_whitespace().next().is_none() {
+            return;
+        }
+        let start_word = self.text.split_whitespace().count();
+        if !self.text.is_empty() {
+            self.text.push_str("\n\n");
+        }
+        self.text.push_str(text);
+        self.chapters.push(Chapter { title, start_word });
+    }
+
+    /// Puts spaces between the words `lang`'s segmenter finds in text
+    /// written without them (Chinese, Japanese, Thai), so each is read on its
+    /// own. Chapter starts and emphasis follow the words they belong to.
+    /// Text that needs no splitting is returned unchanged.
+    pub fn segmented(self, lang: &str) -> Self {
+        if !self.text.split_whitespace().any(segment::needs_segmenting) {
+            return self;
+        }
+        let Some(segmenter) = segment::for_language(lang, &self.text) else {
+            return self;
+        };
+        let source_key = self.key();
+
+        let mut text = String::with_capacity(self.text.len() * 2);
+        let mut emphasis = Vec::with_capacity(self.emphasis.len());
+        // First new word index of each old word, plus the new total
+        let mut new_index = Vec::new();
+        let mut words = 0;
+        let mut copied = 0;
+        for (index, word) in self.text.split_whitespace().enumerate() {
+            let start = word.as_ptr() as usize - self.text.as_ptr() as usize;
+            text.push_str(&self.text[copied..start]);
+            copied = start + word.len();
+
+            let units = if segment::needs_segmenting(word) {
+                segmenter.split(word)
+            } else {
+                vec![word]
+            };
+            text.push_str(&units.join(" "));
+            if let Some(style) = self.emphasis.get(index) {
+                emphasis.extend(iter::repeat_n(*style, units.len()));
+            }
+            new_index.push(words);
+            words += units.len();
+        }
+        text.push_str(&self.text[copied..]);
+        new_index.push(words);
+
+        let chapters = self
+            .chapters
+            .into_iter()
+            .map(|chapter| Chapter {
+                start_word: new_index.get(chapter.start_word).copied().unwrap_or(words),
+                ..chapter
+            })
+            .collect();
+        Self {
+            source_key: Some(source_key),
+            text,
+            chapters,
+            emphasis,
+        }
+    }
+
+    /// Identifies the document for saved positions and bookmarks. Taken from
+    /// the text before segmenting, so it doesn't depend on `lang`; the word
+    /// indices saved under it do, and are checked against `total_words`.
+    pub fn key(&self) -> String {
+        self.source_key
+            .clone()
+            .unwrap_or_else(|| position::document_key(&self.text))
+    }
+
+    pub fn total_words(&self) -> usize {
+        self.text.split_whitespace().count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segmenting_keeps_the_document_key() {
+        let text = "私は日本語を勉強しています。";
+        let segmented = Document::from_text(text.to_string()).segmented("ja");
+        let unsegmented = Document::from_text(text.to_string()).segmented("none");
+        assert_ne!(segmented.text, unsegmented.text);
+        assert_eq!(segmented.key(), unsegmented.key());
+        assert_eq!(segmented.key(), position::document_key(text));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{
+    document::Document,
+    html::{self, Node},
+};
+use color_eyre::Result;
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+use zip::ZipArchive;
+
+type Archive = ZipArchive<File>;
+
+/// Loads an EPUB: locates the OPF package through `META-INF/container.xml`,
+/// follows the spine order and converts each XHTML chapter to text. Chapter
+/// titles come from the table of contents (EPUB 3 nav or EPUB 2 NCX), then
+/// the chapter's first heading, then its position.
+pub fn load(path: &Path) -> Result<Document> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    let container = read_entry(&mut archive, "META-INF/container.xml")?;
+    let opf_path = html::parse(&container)
+        .iter()
+        .find(|node| matches!(node, Node::Open { name, .. } if name == "rootfile"))
+        .and_then(|node| node.attr("full-path"))
+        .map(str::to_string)
+        .ok_or_else(|| invalid("container.xml has no rootfile"))?;
+
+    let opf = read_entry(&mut archive, &opf_path)?;
+    let package = Package::parse(&opf, &opf_path);
+    if package.spine.is_empty() && package.unlisted.is_empty() {
+        return Err(invalid("package has an empty spine").into());
+    }
+    // A broken entry costs one chapter, not the whole book
+    for id in &package.unlisted {
+        eprintln!("Warning: skipping spine item `{}`, which is not in the manifest", id);
+    }
+
+    let toc_titles = match &package.toc {
+        Some(toc_path) => read_entry(&mut archive, toc_path)
+            .map(|toc| toc_titles(&toc, toc_path))
+            .unwrap_or_default(),
+        None => HashMap::new(),
+    };
+
+    let mut document = Document::default();
+    for (index, chapter_path) in package.spine.iter().enumerate() {
+        let markup = match read_entry(&mut archive, chapter_path) {
+            Ok(markup) => markup,
+            Err(e) => {
+                eprintln!("Warning: skipping chapter: {}", e);
+                continue;
+            }
+        };
+        let title = toc_titles
+            .get(chapter_path)
+            .cloned()
+            .or_else(|| html::first_heading(&markup))
+            .unwrap_or_else(|| format!("Chapter {}", index + 1));
+        document.push_chapter(title, &html::to_text(&markup));
+    }
+    if document.chapters.is_empty() {
+        return Err(invalid("no chapter in the spine could be read").into());
+    }
+
+    Ok(document)
+}
+
+/// The parts of the OPF package document needed for reading.
+struct Package {
+    /// Archive paths of the spine's content documents, in reading order.
+    spine: Vec<String>,
+    /// Spine idrefs with no manifest item.
+    unlisted: Vec<String>,
+    /// Archive path of the navigation document or NCX, if declared.
+    toc: Option<String>,
+}
+
+impl Package {
+    fn parse(opf: &str, opf_path: &str) -> Self {
+        let nodes = html::parse(opf);
+
+        // manifest id -> archive path
+        let mut manifest: HashMap<&str, String> = HashMap::new();
+        let mut nav = None;
+        let mut ncx_id = None;
+        let mut spine_ids = Vec::new();
+
+        for node in &nodes {
+            let Node::Open { name, .. } = node else {
+                continue;
+            };
+            match name.as_str() {
+                "item" => {
+                    let (Some(id), Some(href)) = (node.attr("id"), node.attr("href")) else {
+                        continue;
+                    };
+                    let path = resolve(opf_path, href);
+                    let properties = node.attr("properties").unwrap_or("");
+                    if properties.split_whitespace().any(|p| p == "nav") {
+                        nav = Some(path.clone());
+                    }
+                    manifest.insert(id, path);
+                }
+                "spine" => ncx_id = node.attr("toc"),
+                "itemref" if node.attr("linear") != Some("no") => {
+                    if let Some(idref) = node.attr("idref") {
+                        spine_ids.push(idref);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut spine = Vec::new();
+        let mut unlisted = Vec::new();
+        for id in spine_ids {
+            match manifest.get(id) {
+                Some(path) => spine.push(path.clone()),
+                None => unlisted.push(id.to_string()),
+            }
+        }
+        let ncx = ncx_id.and_then(|id| manifest.get(id).cloned());
+
+        Self {
+            spine,
+            unlisted,
+            toc: nav.or(ncx),
+        }
+    }
+}
+
+/// Maps chapter archive paths to their table-of-contents labels. Handles
+/// both EPUB 3 `<nav>` documents (`<a href>` labels) and EPUB 2 NCX files
+/// (`<navLabel><text>` followed by `<content src>`). The first label seen
+/// for a path wins, so nested entries do not overwrite their chapter.
+fn toc_titles(toc: &str, toc_path: &str) -> HashMap<String, String> {
+    let mut titles = HashMap::new();
+    let mut label = String::new();
+    let mut pending_href: Option<String> = None;
+    let mut in_label = false;
+
+    for node in html::parse(toc) {
+        match &node {
+            Node::Open { name, .. } if name == "a" => {
+                pending_href = node.attr("href").map(|href| resolve(toc_path, href));
+                label.clear();
+                in_label = true;
+            }
+            Node::Open { name, .. } if name == "text" => {
+                label.clear();
+                in_label = true;
+            }
+            Node::Open { name, .. } if name == "content" => {
+                if let Some(src) = node.attr("src") {
+                    insert_title(&mut titles, resolve(toc_path, src), &label);
+                }
+            }
+            Node::Close(name) if name == "a" => {
+                if let Some(href) = pending_href.take() {
+                    insert_title(&mut titles, href, &label);
+                }
+                in_label = false;
+            }
+            Node::Close(name) if name == "text" => in_label = false,
+            Node::Text(text) if in_label => {
+                label.push_str(&html::decode_entities(text));
+                label.push(' ');
+            }
+            _ => {}
+        }
+    }
+
+    titles
+}
+
+fn insert_title(titles: &mut HashMap<String, String>, path: String, label: &str) {
+    let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !label.is_empty() {
+        titles.entry(path).or_insert(label);
+    }
+}
+
+fn read_entry(archive: &mut Archive, path: &str) -> Result<String> {
+    let mut entry = archive
+        .by_name(path)
+        .map_err(|_| invalid(&format!("missing {} in archive", path)))?;
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Resolves `href` relative to the archive file `base`, dropping any
+/// `#fragment` and decoding `%XX` escapes.
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let mut parts: Vec<&str> = base.split('/').collect();
+    parts.pop(); // the base file itself
+
+    let decoded = percent_decode(href);
+    for part in decoded.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Invalid EPUB: {}", message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    const OPF: &str = r#"<?xml version="1.0"?>
+        <package xmlns="http://www.idpf.org/2007/opf">
+          <manifest>
+            <item id="toc" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+            <item id="c2" href="text/two.xhtml"/>
+            <item id="c1" href="text/one%20a.xhtml"/>
+            <item id="notes" href="text/notes.xhtml"/>
+          </manifest>
+          <spine toc="toc">
+            <itemref idref="c1"/>
+            <itemref idref="notes" linear="no"/>
+            <itemref idref="missing"/>
+            <itemref idref="c2"/>
+          </spine>
+        </package>"#;
+
+    #[test]
+    fn spine_order_wins_over_manifest_order() {
+        let package = Package::parse(OPF, "OEBPS/content.opf");
+        assert_eq!(package.spine, ["OEBPS/text/one a.xhtml", "OEBPS/text/two.xhtml"]);
+        assert_eq!(package.unlisted, ["missing"]);
+        assert_eq!(package.toc.as_deref(), Some("OEBPS/toc.ncx"));
+    }
+
+    #[test]
+    fn toc_titles_cover_nav_and_ncx() {
+        let nav = r#"<nav epub:type="toc"><ol>
+            <li><a href="text/one.xhtml">One &amp; only</a>
+              <ol><li><a href="text/one.xhtml#part">Part</a></li></ol></li>
+            <li><a href="../two.xhtml">Two</a></li>
+        </ol></nav>"#;
+        let titles = toc_titles(nav, "OEBPS/nav.xhtml");
+        assert_eq!(titles["OEBPS/text/one.xhtml"], "One & only");
+        assert_eq!(titles["two.xhtml"], "Two");
+
+        let ncx = r#"<ncx><navMap><navPoint>
+            <navLabel><text>Opening</text></navLabel><content src="one.xhtml"/>
+        </navPoint></navMap></ncx>"#;
+        assert_eq!(toc_titles(ncx, "toc.ncx")["one.xhtml"], "Opening");
+    }
+
+    #[test]
+    fn load_reads_chapters_in_spine_order_and_skips_missing_ones() {
+        let path = std::env::temp_dir().join(format!("spr-test-{}.epub", std::process::id()));
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let entries = [
+            (
+                "META-INF/container.xml",
+                r#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#,
+            ),
+            ("OEBPS/content.opf", OPF),
+            ("OEBPS/text/two.xhtml", "<html><body><h1>Second</h1><p>Later words.</p></body></html>"),
+            ("OEBPS/text/one a.xhtml", "<html><body><p>First words here.</p></body></html>"),
+        ];
+        for (name, contents) in entries {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let document = load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let document = document.unwrap();
+        assert_eq!(
+            document.text.split_whitespace().collect::<Vec<_>>(),
+            ["First", "words", "here.", "Second", "Later", "words."]
+        );
+        let chapters: Vec<(&str, usize)> = document
+            .chapters
+            .iter()
+            .map(|chapter| (chapter.title.as_str(), chapter.start_word))
+            .collect();
+        // The NCX is missing, so titles fall back to headings and positions
+        assert_eq!(chapters, [("Chapter 1", 0), ("Second", 3)]);
+    }
+}
@@ -1,5 +1,6 @@
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crate::keys::{Action, Keymap};
 use std::time::Duration;
 
 pub enum AppEvent {
@@ -10,23 +11,81 @@ pub enum AppEvent {
     ScrollDown,
     FastForward,
     Rewind,
+    SpeedUp,
+    SlowDown,
+    PrevSentence,
+    NextSentence,
+    PrevParagraph,
+    NextParagraph,
+    StartSearch,
+    NextMatch,
+    PrevMatch,
+    SetMark,
+    JumpToMark,
+    /// Text input while a prompt is open
+    InputChar(char),
+    InputBackspace,
+    InputSubmit,
+    InputCancel,
     Continue,
 }
 
-pub fn handle_events(timeout: Duration) -> Result<AppEvent> {
+/// Reads the next key and maps it through `keymap`. With `text_input` set
+/// (a prompt or a mark command is waiting for its argument), printable keys
+/// are returned as `InputChar` instead.
+pub fn handle_events(timeout: Duration, text_input: bool, keymap: &Keymap) -> Result<AppEvent> {
     if event::poll(timeout)? {
         if let Event::Key(key) = event::read()? {
-            match key.code {
-                KeyCode::Esc | KeyCode::Char('q') => return Ok(AppEvent::Quit),
-                KeyCode::Char(' ') => return Ok(AppEvent::TogglePause),
-                KeyCode::Char('?') => return Ok(AppEvent::ToggleHelp),
-                KeyCode::Char('j') | KeyCode::Down => return Ok(AppEvent::ScrollDown),
-                KeyCode::Char('k') | KeyCode::Up => return Ok(AppEvent::ScrollUp),
-                KeyCode::Char('l') | KeyCode::Right => return Ok(AppEvent::FastForward),
-                KeyCode::Char('h') | KeyCode::Left => return Ok(AppEvent::Rewind),
-                _ => {}
+            if key.kind != KeyEventKind::Press {
+                return Ok(AppEvent::Continue);
+            }
+            if text_input {
+                return Ok(match key.code {
+                    KeyCode::Char(c) => AppEvent::InputChar(c),
+                    KeyCode::Backspace => AppEvent::InputBackspace,
+                    KeyCode::Enter => AppEvent::InputSubmit,
+                    KeyCode::Esc => AppEvent::InputCancel,
+                    _ => AppEvent::Continue,
+                });
+            }
+            if let Some(action) = keymap.action_for(&key) {
+                return Ok(event_for(action));
             }
         }
     }
     Ok(AppEvent::Continue)
 }
+
+fn event_for(action: Action) -> AppEvent {
+    match action {
+        Action::Quit => AppEvent::Quit,
+        Action::Pause => AppEvent::TogglePause,
+        Action::Help => AppEvent::ToggleHelp,
+        Action::ScrollUp => AppEvent::ScrollUp,
+        Action::ScrollDown => AppEvent::ScrollDown,
+        Action::SeekForward => AppEvent::FastForward,
+        Action::SeekBackward => AppEvent::Rewind,
+        Action::SpeedUp => AppEvent::SpeedUp,
+        Action::SlowDown => AppEvent::SlowDown,
+        Action::PrevSentence => AppEvent::PrevSentence,
+        Action::NextSentence => AppEvent::NextSentence,
+        Action::PrevParagraph => AppEvent::PrevParagraph,
+        Action::NextParagraph => AppEvent::NextParagraph,
+        Action::Search => AppEvent::StartSearch,
+        Action::NextMatch => AppEvent::NextMatch,
+        Action::PrevMatch => AppEvent::PrevMatch,
+        Action::SetMark => AppEvent::SetMark,
+        Action::JumpToMark => AppEvent::JumpToMark,
+    }
+}
+
+/// Blocks until any key is pressed.
+pub fn wait_for_key() -> Result<()> {
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                return Ok(());
+            }
+        }
+    }
+}
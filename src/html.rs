@@ -0,0 +1,478 @@
+/// A piece of markup produced by [`parse`]: a tag or the text between tags.
+#[derive(Debug)]
+pub enum Node<'a> {
+    Open {
+        name: String,
+        attrs: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    Close(String),
+    Text(&'a str),
+}
+
+impl Node<'_> {
+    /// Looks up an attribute value on an opening tag.
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            Node::Open { attrs, .. } => attrs
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Elements whose content is raw text that must not be scanned for tags.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Elements that start a new paragraph when converted to text.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "dd", "div", "dl", "dt", "figcaption",
+    "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav",
+    "ol", "p", "pre", "section", "table", "td", "th", "tr", "ul",
+];
+
+/// Elements whose content is never shown as reading text.
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "template", "svg", "math"];
+
+/// Splits HTML or XML into tags and text. This is a forgiving scanner, not a
+/// validating parser: comments, doctypes and processing instructions are
+/// dropped, tag and attribute names are lowercased with any namespace prefix
+/// removed, and CDATA sections are returned as text.
+pub fn parse(markup: &str) -> Vec<Node<'_>> {
+    let mut nodes = Vec::new();
+    let mut rest = markup;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            nodes.push(Node::Text(rest));
+            break;
+        };
+        if lt > 0 {
+            nodes.push(Node::Text(&rest[..lt]));
+        }
+        rest = &rest[lt..];
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").unwrap_or(after.len());
+            nodes.push(Node::Text(&after[..end]));
+            rest = after.get(end + 3..).unwrap_or("");
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').unwrap_or(after.len());
+            nodes.push(Node::Close(local_name(after[..end].trim())));
+            rest = after.get(end + 1..).unwrap_or("");
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let end = tag_end(rest);
+            let node = parse_open_tag(&rest[1..end]);
+            rest = rest.get(end + 1..).unwrap_or("");
+
+            // Skip straight to the closing tag of raw text elements
+            if let Node::Open {
+                name,
+                self_closing: false,
+                ..
+            } = &node
+            {
+                if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                    let close = format!("</{}", name);
+                    let body_end = find_ascii_case_insensitive(rest, &close).unwrap_or(rest.len());
+                    let name = name.clone();
+                    nodes.push(node);
+                    nodes.push(Node::Text(&rest[..body_end]));
+                    nodes.push(Node::Close(name));
+                    rest = &rest[body_end..];
+                    rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+                    continue;
+                }
+            }
+            nodes.push(node);
+        } else {
+            // A stray '<' that does not start a tag is ordinary text
+            nodes.push(Node::Text(&rest[..1]));
+            rest = &rest[1..];
+        }
+    }
+
+    nodes
+}
+
+/// Converts HTML or XHTML to plain text suitable for tokenizing: hidden
+/// elements are dropped, whitespace inside text is collapsed, entities are
+/// decoded and block elements are separated by blank lines so they become
+/// paragraph boundaries.
+pub fn to_text(markup: &str) -> String {
+    nodes_to_text(&parse(markup), |_| false)
+}
+
+/// Elements that hold page chrome rather than article content.
+const CHROME_ELEMENTS: &[&str] = &[
+    "nav", "footer", "aside", "form", "button", "noscript", "iframe", "dialog",
+];
+
+/// Elements that hold the page or its article and are never chrome.
+const ROOT_ELEMENTS: &[&str] = &["html", "body", "main", "article"];
+
+/// ARIA roles that mark page chrome.
+const CHROME_ROLES: &[&str] = &[
+    "navigation", "banner", "contentinfo", "complementary", "search", "menu", "dialog",
+];
+
+/// `class` / `id` words that usually mark page chrome.
+const CHROME_NAMES: &[&str] = &[
+    "nav", "navbar", "menu", "footer", "sidebar", "share", "sharing", "social", "comments",
+    "comment", "breadcrumb", "breadcrumbs", "cookie", "cookies", "related", "newsletter", "promo",
+    "subscribe", "advert", "advertisement", "ads", "skip-link",
+];
+
+/// Extracts the readable article from a web page, in the spirit of
+/// reader-mode tools: the largest `<article>` (else `<main>`, else the whole
+/// page) is converted to text with navigation, headers, footers, sidebars
+/// and other chrome removed. A `<header>` inside the chosen article usually
+/// holds its title, so it is only treated as chrome for whole pages. The
+/// elements around the longest run of text are the content itself and are
+/// kept whatever they are called.
+pub fn extract_article(markup: &str) -> String {
+    let nodes = parse(markup);
+    let article = ["article", "main"]
+        .iter()
+        .find_map(|tag| largest_element(&nodes, tag));
+    let content = article.unwrap_or(&nodes);
+    let ancestors = content_ancestors(content);
+    let is_content = |node: &Node| ancestors.iter().any(|&ancestor| std::ptr::eq(ancestor, node));
+    nodes_to_text(content, |node| {
+        let is_header = matches!(node, Node::Open { name, .. } if name == "header");
+        !is_content(node) && (is_chrome(node) || (article.is_none() && is_header))
+    })
+}
+
+/// Opening tags of the elements enclosing the longest visible text in
+/// `nodes`, outermost first.
+fn content_ancestors<'n, 'a>(nodes: &'n [Node<'a>]) -> Vec<&'n Node<'a>> {
+    let mut open: Vec<&Node> = Vec::new();
+    let mut best: Option<(usize, Vec<&Node>)> = None;
+    for node in nodes {
+        match node {
+            Node::Open { name, self_closing: false, .. } if !is_void(name) => open.push(node),
+            Node::Close(name) => {
+                let opened_here =
+                    |open: &&Node| matches!(open, Node::Open { name: opened, .. } if opened == name);
+                if let Some(pos) = open.iter().rposition(opened_here) {
+                    open.truncate(pos);
+                }
+            }
+            Node::Text(text) => {
+                let hidden = open.iter().any(|open| {
+                    matches!(open, Node::Open { name, .. } if HIDDEN_ELEMENTS.contains(&name.as_str()))
+                });
+                let len = text.trim().len();
+                if !hidden && best.as_ref().is_none_or(|(best_len, _)| len > *best_len) {
+                    best = Some((len, open.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+    best.map(|(_, ancestors)| ancestors).unwrap_or_default()
+}
+
+/// Whether `node` opens an element that is page chrome. The page and
+/// article roots never are, whatever state classes they carry.
+fn is_chrome(node: &Node) -> bool {
+    let Node::Open { name, .. } = node else {
+        return false;
+    };
+    if ROOT_ELEMENTS.contains(&name.as_str()) {
+        return false;
+    }
+    if CHROME_ELEMENTS.contains(&name.as_str()) || node.attr("hidden").is_some() {
+        return true;
+    }
+    if node.attr("aria-hidden") == Some("true") {
+        return true;
+    }
+    if node.attr("role").is_some_and(|role| CHROME_ROLES.contains(&role)) {
+        return true;
+    }
+    // Names match whole (`skip-link`) or by part (`site-footer`, `nav_menu`)
+    ["class", "id"].iter().any(|key| {
+        node.attr(key).is_some_and(|value| {
+            value.split_whitespace().any(|word| {
+                let word = word.to_ascii_lowercase();
+                CHROME_NAMES.contains(&word.as_str())
+                    || word.split(['-', '_']).any(|part| CHROME_NAMES.contains(&part))
+            })
+        })
+    })
+}
+
+/// The nodes inside the `tag` element with the most text, if any.
+fn largest_element<'n, 'a>(nodes: &'n [Node<'a>], tag: &str) -> Option<&'n [Node<'a>]> {
+    let mut best: Option<(&[Node], usize)> = None;
+    let mut i = 0;
+    while i < nodes.len() {
+        if !matches!(&nodes[i], Node::Open { name, self_closing: false, .. } if name == tag) {
+            i += 1;
+            continue;
+        }
+        // Find the matching close tag, allowing for nesting
+        let mut depth = 0;
+        let mut end = nodes.len();
+        for (j, node) in nodes.iter().enumerate().skip(i) {
+            match node {
+                Node::Open { name, self_closing: false, .. } if name == tag => depth += 1,
+                Node::Close(name) if name == tag => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = j;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let inner = &nodes[i + 1..end];
+        // Script and style bodies are code, not reading text
+        let mut size = 0;
+        let mut in_raw_text = false;
+        for node in inner {
+            match node {
+                Node::Open { name, self_closing: false, .. }
+                    if RAW_TEXT_ELEMENTS.contains(&name.as_str()) =>
+                {
+                    in_raw_text = true
+                }
+                Node::Close(name) if RAW_TEXT_ELEMENTS.contains(&name.as_str()) => {
+                    in_raw_text = false
+                }
+                Node::Text(text) if !in_raw_text => size += text.trim().len(),
+                _ => {}
+            }
+        }
+        if best.is_none_or(|(_, best_size)| size > best_size) {
+            best = Some((inner, size));
+        }
+        i = end.max(i + 1);
+    }
+    best.filter(|(_, size)| *size > 0).map(|(inner, _)| inner)
+}
+
+/// Whether `source` looks like an HTML document rather than plain text.
+pub fn looks_like_html(source: &str) -> bool {
+    let head: String = source.trim_start().chars().take(256).collect();
+    let head = head.to_ascii_lowercase();
+    head.starts_with("<!doctype html")
+        || head.starts_with("<html")
+        || (head.starts_with("<?xml") && head.contains("<html"))
+}
+
+/// Like [`to_text`], additionally dropping any element for which `skip`
+/// returns true along with everything inside it.
+pub fn nodes_to_text(nodes: &[Node], skip: impl Fn(&Node) -> bool) -> String {
+    let mut text = String::new();
+    // Stack of element names being skipped; content is emitted only when empty
+    let mut skipping: Vec<&str> = Vec::new();
+
+    for node in nodes {
+        match node {
+            Node::Open {
+                name, self_closing, ..
+            } => {
+                if !skipping.is_empty() || HIDDEN_ELEMENTS.contains(&name.as_str()) || skip(node)
+                {
+                    if !self_closing && !is_void(name) {
+                        skipping.push(name);
+                    }
+                } else if name == "br" {
+                    text.push('\n');
+                } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                    text.push_str("\n\n");
+                }
+            }
+            Node::Close(name) => {
+                if let Some(pos) = skipping.iter().rposition(|open| open == name) {
+                    skipping.truncate(pos);
+                } else if skipping.is_empty() && BLOCK_ELEMENTS.contains(&name.as_str()) {
+                    text.push_str("\n\n");
+                }
+            }
+            Node::Text(raw) if skipping.is_empty() => {
+                for c in decode_entities(raw).chars() {
+                    if !c.is_whitespace() {
+                        text.push(c);
+                    } else if !text.is_empty() && !text.ends_with(char::is_whitespace) {
+                        text.push(' ');
+                    }
+                }
+            }
+            Node::Text(_) => {}
+        }
+    }
+
+    text
+}
+
+/// Text of the first `<h1>`–`<h3>`, falling back to `<title>`.
+pub fn first_heading(markup: &str) -> Option<String> {
+    let nodes = parse(markup);
+    ["h1", "h2", "h3", "title"]
+        .iter()
+        .find_map(|tag| element_text(&nodes, tag))
+}
+
+/// Collapsed text content of the first `tag` element in `nodes`.
+fn element_text(nodes: &[Node], tag: &str) -> Option<String> {
+    let start = nodes
+        .iter()
+        .position(|node| matches!(node, Node::Open { name, .. } if name == tag))?;
+    let mut text = String::new();
+    for node in &nodes[start + 1..] {
+        match node {
+            Node::Close(name) if name == tag => break,
+            Node::Text(raw) => {
+                text.push_str(&decode_entities(raw));
+                text.push(' ');
+            }
+            _ => {}
+        }
+    }
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!collapsed.is_empty()).then_some(collapsed)
+}
+
+/// Decodes named (the full HTML5 set), decimal and hexadecimal character
+/// references. Unknown references are left as written.
+pub fn decode_entities(text: &str) -> String {
+    html_escape::decode_html_entities(text).into_owned()
+}
+
+/// Index of the `>` closing the tag starting at `tag[0] == '<'`, skipping
+/// over quoted attribute values.
+fn tag_end(tag: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return i,
+            _ => {}
+        }
+    }
+    tag.len()
+}
+
+fn parse_open_tag(inner: &str) -> Node<'static> {
+    let self_closing = inner.ends_with('/');
+    let inner = inner.trim_end_matches('/');
+    let name_end = inner
+        .find(|c: char| c.is_whitespace())
+        .unwrap_or(inner.len());
+    let name = local_name(&inner[..name_end]);
+
+    let mut attrs = Vec::new();
+    let mut rest = inner[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = local_name(&rest[..key_end]);
+        rest = rest[key_end..].trim_start();
+
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, remainder) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let end = after[1..].find(q).map_or(after.len(), |e| e + 1);
+                    (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace())
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            rest = remainder.trim_start();
+            decode_entities(value)
+        } else {
+            String::new()
+        };
+
+        if !key.is_empty() {
+            attrs.push((key, value));
+        }
+    }
+
+    Node::Open {
+        name,
+        attrs,
+        self_closing,
+    }
+}
+
+/// Lowercases a tag or attribute name and strips any `prefix:`.
+fn local_name(name: &str) -> String {
+    let local = name.rsplit(':').next().unwrap_or(name);
+    local.to_ascii_lowercase()
+}
+
+fn is_void(name: &str) -> bool {
+    matches!(
+        name,
+        "br" | "hr" | "img" | "input" | "meta" | "link" | "area" | "base" | "col" | "embed"
+            | "source" | "track" | "wbr"
+    )
+}
+
+fn find_ascii_case_insensitive(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<&str> {
+        text.split_whitespace().collect()
+    }
+
+    #[test]
+    fn page_roots_are_never_chrome() {
+        let page = r#"<html><body class="single has-sidebar"><p>Real article text</p></body></html>"#;
+        assert_eq!(words(&extract_article(page)), ["Real", "article", "text"]);
+    }
+
+    #[test]
+    fn wrappers_around_the_content_are_never_chrome() {
+        let page = r#"<html><body>
+            <div id="page" class="site menu-closed">
+              <div class="site-nav"><a href="/">Home</a></div>
+              <div class="content"><p>The story goes on for a while here.</p></div>
+              <div class="social-share">Share this</div>
+            </div>
+        </body></html>"#;
+        assert_eq!(
+            words(&extract_article(page)),
+            ["The", "story", "goes", "on", "for", "a", "while", "here."]
+        );
+    }
+
+    #[test]
+    fn chrome_inside_the_article_is_dropped() {
+        let page = r#"<body><nav>Menu</nav><article class="post has-comments">
+            <h1>Title</h1><p>Body text of the article.</p>
+            <aside>Related</aside><div class="post-comments">Nice!</div>
+        </article></body>"#;
+        assert_eq!(
+            words(&extract_article(page)),
+            ["Title", "Body", "text", "of", "the", "article."]
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, io};
+
+/// Every command that can be bound to keys in the `[keys]` config table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Pause,
+    Help,
+    ScrollUp,
+    ScrollDown,
+    SeekForward,
+    SeekBackward,
+    SpeedUp,
+    SlowDown,
+    PrevSentence,
+    NextSentence,
+    PrevParagraph,
+    NextParagraph,
+    Search,
+    NextMatch,
+    PrevMatch,
+    SetMark,
+    JumpToMark,
+}
+
+impl Action {
+    /// All actions, in the order they are listed in the help popup.
+    pub const ALL: [Action; 18] = [
+        Action::Quit,
+        Action::Pause,
+        Action::SeekBackward,
+        Action::SeekForward,
+        Action::SpeedUp,
+        Action::SlowDown,
+        Action::PrevSentence,
+        Action::NextSentence,
+        Action::PrevParagraph,
+        Action::NextParagraph,
+        Action::Search,
+        Action::NextMatch,
+        Action::PrevMatch,
+        Action::SetMark,
+        Action::JumpToMark,
+        Action::Help,
+        Action::ScrollDown,
+        Action::ScrollUp,
+    ];
+
+    /// Name used in the `[keys]` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Pause => "pause",
+            Action::Help => "help",
+            Action::ScrollUp => "scroll_up",
+            Action::ScrollDown => "scroll_down",
+            Action::SeekForward => "seek_forward",
+            Action::SeekBackward => "seek_backward",
+            Action::SpeedUp => "speed_up",
+            Action::SlowDown => "slow_down",
+            Action::PrevSentence => "prev_sentence",
+            Action::NextSentence => "next_sentence",
+            Action::PrevParagraph => "prev_paragraph",
+            Action::NextParagraph => "next_paragraph",
+            Action::Search => "search",
+            Action::NextMatch => "next_match",
+            Action::PrevMatch => "prev_match",
+            Action::SetMark => "set_mark",
+            Action::JumpToMark => "jump_to_mark",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "esc"],
+            Action::Pause => &["space"],
+            Action::Help => &["?"],
+            Action::ScrollUp => &["k", "up"],
+            Action::ScrollDown => &["j", "down"],
+            Action::SeekForward => &["l", "right"],
+            Action::SeekBackward => &["h", "left"],
+            Action::SpeedUp => &["+", "="],
+            Action::SlowDown => &["-", "_"],
+            Action::PrevSentence => &["("],
+            Action::NextSentence => &[")"],
+            Action::PrevParagraph => &["{"],
+            Action::NextParagraph => &["}"],
+            Action::Search => &["/"],
+            Action::NextMatch => &["n"],
+            Action::PrevMatch => &["N"],
+            Action::SetMark => &["m"],
+            Action::JumpToMark => &["'"],
+        }
+    }
+
+    /// Help popup description. `seek_step` and `wpm_step` fill in amounts.
+    pub fn description(self, seek_step: usize, wpm_step: u64) -> String {
+        match self {
+            Action::Quit => "Quit".to_string(),
+            Action::Pause => "Pause / Resume".to_string(),
+            Action::Help => "Toggle this help".to_string(),
+            Action::ScrollUp => "Scroll help up".to_string(),
+            Action::ScrollDown => "Scroll help down".to_string(),
+            Action::SeekForward => format!("Fast-forward {} words", seek_step),
+            Action::SeekBackward => format!("Rewind {} words (sentence when paused)", seek_step),
+            Action::SpeedUp => format!("Speed up {} wpm", wpm_step),
+            Action::SlowDown => format!("Slow down {} wpm", wpm_step),
+            Action::PrevSentence => "Previous sentence".to_string(),
+            Action::NextSentence => "Next sentence".to_string(),
+            Action::PrevParagraph => "Previous paragraph".to_string(),
+            Action::NextParagraph => "Next paragraph".to_string(),
+            Action::Search => "Search".to_string(),
+            Action::NextMatch => "Next match".to_string(),
+            Action::PrevMatch => "Previous match".to_string(),
+            Action::SetMark => "Set bookmark, then a-z".to_string(),
+            Action::JumpToMark => "Jump to bookmark, then a-z".to_string(),
+        }
+    }
+}
+
+/// A key plus modifiers, written in config as e.g. `"q"`, `"space"`,
+/// `"ctrl+c"` or `"shift+tab"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let (mods, key) = match text.strip_suffix('+') {
+            // The key itself is '+', alone or after modifiers as in "ctrl++"
+            Some("") => (None, "+"),
+            Some(rest) if rest.ends_with('+') => (Some(&rest[..rest.len() - 1]), "+"),
+            _ => match text.rsplit_once('+') {
+                Some((mods, key)) => (Some(mods), key),
+                None => (None, text),
+            },
+        };
+        if key.is_empty() {
+            return Err(match text {
+                "" => "empty key".to_string(),
+                _ => format!("no key after the last `+` in `{}`", text),
+            });
+        }
+
+        for modifier in mods.into_iter().flat_map(|mods| mods.split('+')) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "" => return Err(format!("empty modifier in `{}`", text)),
+                other => return Err(format!("unknown modifier `{}`", other)),
+            };
+        }
+
+        let code = match key.to_ascii_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                KeyCode::F(f[1..].parse().unwrap_or(1))
+            }
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key `{}`", key)),
+                }
+            }
+        };
+
+        Ok(Self::normalized(code, modifiers))
+    }
+
+    /// Folds Shift into printable characters so `"shift+n"`, `"N"` and a
+    /// terminal reporting `N` with the Shift modifier all compare equal.
+    /// Shift+Tab is reported as its own key, `BackTab`, with or without the
+    /// Shift modifier, so `"shift+tab"` becomes that key.
+    fn normalized(code: KeyCode, mut modifiers: KeyModifiers) -> Self {
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    fn from_event(key: &KeyEvent) -> Self {
+        let modifiers =
+            key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        Self::normalized(key.code, modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// One or more key chords for an action in the `[keys]` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    fn keys(&self) -> Vec<&str> {
+        match self {
+            KeyBinding::One(key) => vec![key.as_str()],
+            KeyBinding::Many(keys) => keys.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// The resolved key bindings: defaults with any `[keys]` overrides applied.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyChord>)>,
+}
+
+impl Keymap {
+    /// Builds the keymap, replacing the default keys of each action named in
+    /// `overrides`. Fails on unknown action names, unparseable keys and keys
+    /// bound to more than one action.
+    pub fn from_config(overrides: &BTreeMap<String, KeyBinding>) -> Result<Self, io::Error> {
+        for name in overrides.keys() {
+            if Action::from_name(name).is_none() {
+                return Err(invalid(format!("unknown action `{}` in [keys]", name)));
+            }
+        }
+
+        let mut bindings = Vec::new();
+        for action in Action::ALL {
+            let keys = match overrides.get(action.name()) {
+                Some(binding) => binding.keys(),
+                None => action.default_keys().to_vec(),
+            };
+            let chords = keys
+                .iter()
+                .map(|key| {
+                    KeyChord::parse(key).map_err(|e| {
+                        invalid(format!("invalid key for `{}` in [keys]: {}", action.name(), e))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.push((action, chords));
+        }
+
+        // Each chord may trigger only one action
+        for (i, (action, chords)) in bindings.iter().enumerate() {
+            for chord in chords {
+                if let Some((other, _)) = bindings[i + 1..].iter().find(|(_, c)| c.contains(chord)) {
+                    return Err(invalid(format!(
+                        "key `{}` is bound to both `{}` and `{}` in [keys]",
+                        chord,
+                        action.name(),
+                        other.name()
+                    )));
+                }
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(_, chords)| chords.contains(&chord))
+            .map(|(action, _)| *action)
+    }
+
+    /// Display text for an action's keys, e.g. `"h / ←"`.
+    pub fn keys_label(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, chords)| {
+                chords
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" / ")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Display text for the first key bound to an action.
+    pub fn primary_key(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, chords)| chords.first())
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+
+    /// `(keys, description)` rows for the help popup.
+    pub fn help_rows(&self, seek_step: usize, wpm_step: u64) -> Vec<(String, String)> {
+        self.bindings
+            .iter()
+            .filter(|(_, chords)| !chords.is_empty())
+            .map(|(action, _)| (self.keys_label(*action), action.description(seek_step, wpm_step)))
+            .collect()
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::from_event(&KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn parses_keys_and_modifiers() {
+        let parse = |text| KeyChord::parse(text).unwrap();
+        assert_eq!(parse("q"), chord(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(parse("space"), chord(KeyCode::Char(' '), KeyModifiers::NONE));
+        assert_eq!(parse("Ctrl+c"), chord(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert_eq!(parse("+"), chord(KeyCode::Char('+'), KeyModifiers::NONE));
+        assert_eq!(parse("ctrl++"), chord(KeyCode::Char('+'), KeyModifiers::CONTROL));
+        assert_eq!(parse("f5"), chord(KeyCode::F(5), KeyModifiers::NONE));
+        // Shift folds into the character, however the terminal reports it
+        assert_eq!(parse("shift+n"), parse("N"));
+        assert_eq!(parse("N"), chord(KeyCode::Char('N'), KeyModifiers::SHIFT));
+    }
+
+    #[test]
+    fn shift_tab_is_back_tab() {
+        let shift_tab = KeyChord::parse("shift+tab").unwrap();
+        assert_eq!(shift_tab, chord(KeyCode::BackTab, KeyModifiers::SHIFT));
+        assert_eq!(shift_tab, chord(KeyCode::BackTab, KeyModifiers::NONE));
+        assert_eq!(shift_tab, KeyChord::parse("backtab").unwrap());
+        assert_ne!(shift_tab, KeyChord::parse("tab").unwrap());
+        assert_eq!(shift_tab.to_string(), "Shift+Tab");
+    }
+
+    #[test]
+    fn rejects_empty_and_unknown_parts() {
+        for text in ["", "a+", "ctrl+", "++", "ctrl++c", "+a", "hyper+a", "escape2"] {
+            assert!(KeyChord::parse(text).is_err(), "`{}` should not parse", text);
+        }
+    }
+
+    #[test]
+    fn overrides_replace_defaults_and_must_not_clash() {
+        let binding = |keys: &[&str]| KeyBinding::Many(keys.iter().map(|k| k.to_string()).collect());
+        let overrides = BTreeMap::from([("pause".to_string(), binding(&["p", "enter"]))]);
+        let keymap = Keymap::from_config(&overrides).unwrap();
+        let event = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(keymap.action_for(&event(KeyCode::Enter)), Some(Action::Pause));
+        assert_eq!(keymap.action_for(&event(KeyCode::Char(' '))), None);
+        assert_eq!(keymap.action_for(&event(KeyCode::Char('q'))), Some(Action::Quit));
+
+        let clash = BTreeMap::from([("pause".to_string(), binding(&["q"]))]);
+        assert!(Keymap::from_config(&clash).is_err());
+        let unknown = BTreeMap::from([("jump".to_string(), binding(&["j"]))]);
+        assert!(Keymap::from_config(&unknown).is_err());
+    }
+}
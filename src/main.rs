@@ -1,36 +1,225 @@
 mod app;
+mod bidi;
+mod bookmarks;
 mod cli;
+mod color;
 mod config;
+mod dirs;
+mod document;
+mod epub;
 mod events;
+mod html;
+mod keys;
+mod markdown;
+mod position;
+mod search;
+mod segment;
 mod state;
+mod stats;
+mod timing;
+mod theme;
+mod tokenize;
 mod tui;
 mod ui;
 
+use bookmarks::BookmarkStore;
 use clap::Parser;
 use color_eyre::Result;
+use document::Document;
+use position::{PositionStore, SavedPosition};
+use std::path::Path;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = cli::Args::parse();
-    let config = config::Config::load()?;
 
-    let content = cli::get_content(&args)?;
+    if let Some(command) = &args.command {
+        return run_command(command, &args);
+    }
+
+    let config = config::Config::load(args.config.as_deref(), &args.config_overrides())?;
+
+    let document = cli::get_content(&args, &config)?;
 
     // Validate content before initializing TUI
-    if content.split_whitespace().collect::<Vec<_>>().is_empty() {
+    let total_words = document.total_words();
+    if total_words == 0 {
         return Err(
             std::io::Error::new(std::io::ErrorKind::InvalidInput, "No words to display").into(),
         );
     }
 
-    // Use CLI arg if provided, otherwise use config value
-    let is_inline = args.inline.unwrap_or(config.inline);
+    // Saved positions only apply to documents read from a file
+    let document_path = args.file.as_deref().filter(|f| *f != "-");
+    let document_key = document.key();
+    // `None` when the file couldn't be read; it is then left alone on exit
+    let mut positions = load_or_warn(PositionStore::load(), "reading positions");
+    let mut bookmark_store = load_or_warn(BookmarkStore::load(), "bookmarks");
+    // Saved word indices only hold for the word count they were saved at
+    let saved_bookmarks = bookmark_store
+        .as_ref()
+        .and_then(|store| store.get(&document_key));
+    let stale_bookmarks = saved_bookmarks.is_some_and(|saved| !saved.applies_to(total_words));
+    if stale_bookmarks {
+        eprintln!(
+            "Note: this document's bookmarks were set when it had a different number of \
+             words (was `lang` changed?); they are kept but not used"
+        );
+    }
+    let mut bookmarks = saved_bookmarks
+        .filter(|_| !stale_bookmarks)
+        .cloned()
+        .unwrap_or_default();
+
+    let saved_word = positions
+        .as_ref()
+        .and_then(|positions| positions.get(&document_key))
+        .filter(|saved| saved.applies_to(total_words))
+        .map(|saved| saved.word)
+        .filter(|_| document_path.is_some() && !args.restart);
+
+    let start_word = match (args.chapter, saved_word) {
+        (Some(number), _) => {
+            let chapter = number
+                .checked_sub(1)
+                .and_then(|index| document.chapters.get(index))
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Chapter {} not found (document has {})",
+                            number,
+                            document.chapters.len()
+                        ),
+                    )
+                })?;
+            chapter.start_word
+        }
+        (None, Some(word)) if args.resume || cli::confirm_resume(word, total_words)? => word,
+        _ => 0,
+    };
+
+    let is_inline = config.inline;
 
     let mut terminal = tui::init(is_inline)?;
 
-    app::run(&content, args.wpm, is_inline, args.preview_words, &mut terminal)?;
+    let session = app::run(
+        &document,
+        &config,
+        args.wpm,
+        start_word,
+        &mut bookmarks,
+        &mut terminal,
+    )?;
 
     tui::restore(is_inline, &mut terminal)?;
 
+    // Inline mode leaves the viewport in the scrollback, so repeat the
+    // summary as plain text below it
+    if is_inline && matches!(session.outcome, app::Outcome::Finished) {
+        for (label, value) in session.stats.summary_rows() {
+            println!("{:<12}{}", label, value);
+        }
+    }
+
+    let (finished, end_word) = match session.outcome {
+        app::Outcome::Finished => (true, total_words - 1),
+        app::Outcome::Quit { word_index } => (false, word_index),
+    };
+    warn_on_error(
+        stats::record(stats::SessionRecord::new(
+            document_key.clone(),
+            document_path.map(str::to_string),
+            total_words,
+            end_word,
+            finished,
+            &session.stats,
+        )),
+        "reading history",
+    );
+
+    if let Some(path) = document_path {
+        if let Some(positions) = &mut positions {
+            match session.outcome {
+                app::Outcome::Finished => positions.remove(&document_key),
+                app::Outcome::Quit { word_index } => positions.set(
+                    &document_key,
+                    SavedPosition {
+                        word: word_index,
+                        total_words,
+                        path: Some(path.to_string()),
+                    },
+                ),
+            }
+            warn_on_error(positions.save(), "reading position");
+        }
+
+        // Unusable marks stay saved unless new ones replace them
+        let replaces = !stale_bookmarks || !bookmarks.marks.is_empty();
+        if let Some(bookmark_store) = bookmark_store.as_mut().filter(|_| replaces) {
+            bookmarks.path = Some(path.to_string());
+            bookmarks.total_words = Some(total_words);
+            bookmark_store.set(&document_key, bookmarks);
+            warn_on_error(bookmark_store.save(), "bookmarks");
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a subcommand instead of opening the reader.
+fn run_command(command: &cli::Command, args: &cli::Args) -> Result<()> {
+    let config_path = args.config.as_deref();
+    match command {
+        cli::Command::Bookmarks { file } => {
+            let config = config::Config::load(config_path, &args.config_overrides())?;
+            let document = Document::from_file(Path::new(file), args.format, &config)?;
+            let key = document.key();
+            let store = BookmarkStore::load()?;
+            bookmarks::print_list(store.get(&key), document.total_words());
+        }
+        cli::Command::Stats => stats::print_summary()?,
+        cli::Command::Config {
+            action: cli::ConfigCommand::Init { force },
+        } => {
+            let path = config::Config::init(config_path, *force)?;
+            println!("Wrote {}", path.display());
+        }
+        cli::Command::Config {
+            action: cli::ConfigCommand::Check,
+        } => {
+            let (path, problems) = config::Config::check(config_path, &args.config_overrides());
+            if !problems.is_empty() {
+                for problem in &problems {
+                    eprintln!("{}", problem);
+                }
+                std::process::exit(1);
+            }
+            match path {
+                Some(path) => println!("{}: ok", path.display()),
+                None => println!("No config file; using defaults (create one with `spr config init`)"),
+            }
+        }
+    }
     Ok(())
 }
+
+/// Loading saved state is best effort: a damaged file is reported and the
+/// reader starts without it, rather than not starting at all.
+fn load_or_warn<T>(result: Result<T>, what: &str) -> Option<T> {
+    match result {
+        Ok(store) => Some(store),
+        Err(e) => {
+            eprintln!("Warning: could not read {}, continuing without them: {}", what, e);
+            None
+        }
+    }
+}
+
+/// Saving is best effort: a read-only state or data directory shouldn't turn
+/// a finished reading session into an error.
+fn warn_on_error(result: Result<()>, what: &str) {
+    if let Err(e) = result {
+        eprintln!("Warning: could not save {}: {}", what, e);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    document::{Chapter, Document, Emphasis},
+    html,
+};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Converts Markdown to a [`Document`]: syntax is stripped, block elements
+/// become paragraphs, level 1–2 headings start sections (reported like
+/// chapters), link targets and images are dropped, and bold/italic runs are
+/// recorded per word. Fenced and indented code blocks are omitted when
+/// `skip_code_blocks` is set.
+pub fn parse(source: &str, skip_code_blocks: bool) -> Document {
+    let mut builder = Builder::default();
+    let mut in_code_block = false;
+    let mut in_image = false;
+    let mut heading_start: Option<(HeadingLevel, usize, usize)> = None;
+    // Markup of the HTML block being read, converted once the block ends
+    let mut html_block: Option<String> = None;
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_TASKLISTS;
+
+    for event in Parser::new_ext(source, options) {
+        if in_code_block && skip_code_blocks && !matches!(event, Event::End(TagEnd::CodeBlock)) {
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                builder.break_paragraph();
+                heading_start = Some((level, builder.emphasis.len(), builder.text.len()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, start_word, start_byte)) = heading_start.take() {
+                    if level <= HeadingLevel::H2 {
+                        let title = builder.text[start_byte..].split_whitespace();
+                        let title = title.collect::<Vec<_>>().join(" ");
+                        if !title.is_empty() {
+                            builder.chapters.push(Chapter { title, start_word });
+                        }
+                    }
+                }
+                builder.break_paragraph();
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                builder.break_paragraph();
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                builder.break_paragraph();
+            }
+            Event::Start(Tag::Emphasis) => builder.italic += 1,
+            Event::End(TagEnd::Emphasis) => builder.italic -= 1,
+            Event::Start(Tag::Strong) => builder.bold += 1,
+            Event::End(TagEnd::Strong) => builder.bold -= 1,
+            Event::Start(Tag::Image { .. }) => in_image = true,
+            Event::End(TagEnd::Image) => in_image = false,
+            Event::Start(
+                Tag::Paragraph
+                | Tag::BlockQuote(_)
+                | Tag::Item
+                | Tag::TableHead
+                | Tag::TableRow
+                | Tag::FootnoteDefinition(_),
+            )
+            | Event::End(
+                TagEnd::Paragraph
+                | TagEnd::BlockQuote(_)
+                | TagEnd::Item
+                | TagEnd::TableHead
+                | TagEnd::TableRow
+                | TagEnd::FootnoteDefinition,
+            )
+            | Event::Rule => builder.break_paragraph(),
+            Event::End(TagEnd::TableCell) | Event::SoftBreak => builder.push_str(" "),
+            Event::HardBreak => builder.push_str("\n"),
+            Event::Text(text) | Event::Code(text) if !in_image => builder.push_str(&text),
+            Event::Start(Tag::HtmlBlock) => {
+                builder.break_paragraph();
+                html_block = Some(String::new());
+            }
+            Event::End(TagEnd::HtmlBlock) => {
+                if let Some(markup) = html_block.take() {
+                    builder.push_str(&html::to_text(&markup));
+                }
+                builder.break_paragraph();
+            }
+            // An HTML block arrives a line at a time
+            Event::Html(markup) => match &mut html_block {
+                Some(block) => block.push_str(&markup),
+                None => builder.push_str(&html::to_text(&markup)),
+            },
+            _ => {}
+        }
+    }
+
+    Document {
+        text: builder.text,
+        chapters: builder.chapters,
+        emphasis: builder.emphasis,
+        ..Document::default()
+    }
+}
+
+/// Accumulates plain text while tracking the emphasis of each word.
+#[derive(Default)]
+struct Builder {
+    text: String,
+    chapters: Vec<Chapter>,
+    /// One entry per word emitted so far.
+    emphasis: Vec<Emphasis>,
+    bold: usize,
+    italic: usize,
+}
+
+impl Builder {
+    fn push_str(&mut self, text: &str) {
+        let current = Emphasis {
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+        };
+        for c in text.chars() {
+            if !c.is_whitespace() {
+                // A word is emphasized if any part of it is
+                if self.text.is_empty() || self.text.ends_with(char::is_whitespace) {
+                    self.emphasis.push(current);
+                } else if let Some(last) = self.emphasis.last_mut() {
+                    last.bold |= current.bold;
+                    last.italic |= current.italic;
+                }
+            }
+            self.text.push(c);
+        }
+    }
+
+    fn break_paragraph(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with("\n\n") {
+            self.text.push_str("\n\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize;
+
+    #[test]
+    fn html_blocks_break_paragraphs_only_at_their_edges() {
+        let document = parse("Before.\n\n<div>\nhello\nworld\n</div>\n\nAfter.", false);
+        let starts: Vec<&str> = tokenize::tokenize(&document.text)
+            .into_iter()
+            .filter(|token| token.paragraph_start)
+            .map(|token| token.text)
+            .collect();
+        assert_eq!(starts, ["Before.", "hello", "After."]);
+    }
+
+    #[test]
+    fn html_blocks_keep_their_own_paragraphs() {
+        let document = parse("<div>\n<p>one</p>\n<p>two</p>\n</div>", false);
+        let tokens = tokenize::tokenize(&document.text);
+        let starts: Vec<(&str, bool)> = tokens
+            .iter()
+            .map(|token| (token.text, token.paragraph_start))
+            .collect();
+        assert_eq!(starts, [("one", true), ("two", true)]);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::dirs;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Where the reader stopped in a document, persisted between runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPosition {
+    pub word: usize,
+    pub total_words: usize,
+    /// Last path the document was opened from, for display only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl SavedPosition {
+    /// Whether the position was saved in a document of `total_words` words.
+    /// Splitting the same text into words differently, as another `lang`
+    /// does, changes the count, and the saved index would then point at the
+    /// wrong word.
+    pub fn applies_to(&self, total_words: usize) -> bool {
+        self.total_words == total_words && self.word < total_words
+    }
+}
+
+/// Saved reading positions keyed by a hash of the document content, so a
+/// position survives the file being moved or renamed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PositionStore {
+    #[serde(default)]
+    documents: BTreeMap<String, SavedPosition>,
+}
+
+impl PositionStore {
+    pub fn load() -> Result<Self> {
+        // Without a state directory there is nothing to resume from
+        let Ok(path) = Self::store_path() else {
+            return Ok(Self::default());
+        };
+        let path = dirs::readable(path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| dirs::with_path(&path, e))?;
+        toml::from_str(&contents).map_err(|e| {
+            dirs::with_path(&path, io::Error::new(io::ErrorKind::InvalidData, e)).into()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        dirs::write(&Self::store_path()?, &toml::to_string_pretty(self)?)
+    }
+
+    pub fn store_path() -> Result<PathBuf> {
+        Ok(dirs::state_dir()?.join("positions.toml"))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SavedPosition> {
+        self.documents.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, position: SavedPosition) {
+        self.documents.insert(key.to_string(), position);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.documents.remove(key);
+    }
+}
+
+/// Stable identifier for a document: 64-bit FNV-1a over its content.
+/// `DefaultHasher` is not used because its output may change between
+/// Rust releases, which would orphan every saved position.
+pub fn document_key(content: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in content.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+
+    #[test]
+    fn positions_only_apply_at_the_word_count_they_were_saved_at() {
+        let text = "私は日本語を勉強しています。";
+        let segmented = Document::from_text(text.to_string()).segmented("ja");
+        let unsegmented = Document::from_text(text.to_string()).segmented("none");
+        // Same document, so the same key, but counted in different words
+        assert_eq!(segmented.key(), unsegmented.key());
+        let saved = SavedPosition {
+            word: 0,
+            total_words: segmented.total_words(),
+            path: None,
+        };
+        assert!(saved.applies_to(segmented.total_words()));
+        assert!(!saved.applies_to(unsegmented.total_words()));
+
+        let past_the_end = SavedPosition { word: 5, ..saved };
+        assert!(!past_the_end.applies_to(5));
+    }
+}
@@ -0,0 +1,56 @@
+/// Results of a `/` search over the word list.
+#[derive(Debug)]
+pub struct Search {
+    pub query: String,
+    /// Word index where each match starts, in reading order.
+    matches: Vec<usize>,
+    /// Index into `matches` of the match last jumped to.
+    current: Option<usize>,
+}
+
+impl Search {
+    pub fn new(query: String, matches: Vec<usize>) -> Self {
+        Self {
+            query,
+            matches,
+            current: None,
+        }
+    }
+
+    /// The first match after `word_index`, wrapping to the start.
+    pub fn next_from(&mut self, word_index: usize) -> Option<usize> {
+        let pos = self
+            .matches
+            .iter()
+            .position(|&m| m > word_index)
+            .or((!self.matches.is_empty()).then_some(0))?;
+        self.current = Some(pos);
+        Some(self.matches[pos])
+    }
+
+    /// The last match before `word_index`, wrapping to the end.
+    pub fn prev_from(&mut self, word_index: usize) -> Option<usize> {
+        let pos = self
+            .matches
+            .iter()
+            .rposition(|&m| m < word_index)
+            .or(self.matches.len().checked_sub(1))?;
+        self.current = Some(pos);
+        Some(self.matches[pos])
+    }
+
+    /// Status text such as `/query  3/12` or `/query  no matches`.
+    pub fn status(&self) -> String {
+        match (self.matches.len(), self.current) {
+            (0, _) => format!("/{}  no matches", self.query),
+            (total, Some(pos)) => format!("/{}  {}/{}", self.query, pos + 1, total),
+            (total, None) => format!("/{}  {} matches", self.query, total),
+        }
+    }
+}
+
+/// Lowercases a word and strips surrounding punctuation for matching.
+pub fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
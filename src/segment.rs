@@ -0,0 +1,290 @@
+use icu_segmenter::WordSegmenter;
+
+/// Breaks a run of text written without spaces between words into display
+/// units. Implementations must return consecutive, non-empty slices that
+/// together cover all of `run`.
+pub trait Segmenter {
+    fn split<'a>(&self, run: &'a str) -> Vec<&'a str>;
+}
+
+/// Chinese: dictionary words.
+pub struct Chinese;
+
+/// Japanese: dictionary words joined into phrases, with the kana after a
+/// content word (verb endings and particles) kept with it and kanji
+/// compounds kept together.
+pub struct Japanese;
+
+/// Thai: dictionary words.
+pub struct Thai;
+
+/// Kanji words are joined into compounds of up to this many characters.
+const MAX_KANJI: usize = 4;
+/// Most kana kept with the content word before them.
+const MAX_KANA_TAIL: usize = 6;
+/// Kana without a content word before them are grouped to at least this
+/// many characters.
+const KANA_GROUP: usize = 3;
+
+impl Segmenter for Chinese {
+    fn split<'a>(&self, run: &'a str) -> Vec<&'a str> {
+        attach_punctuation(run, dictionary_words(run))
+    }
+}
+
+impl Segmenter for Japanese {
+    fn split<'a>(&self, run: &'a str) -> Vec<&'a str> {
+        /// What the last unit ends with, deciding what may join it.
+        #[derive(Clone, Copy)]
+        enum Last {
+            Kanji(usize),
+            /// A content word and the kana added after it.
+            Content(usize),
+            Kana(usize),
+            Other,
+        }
+
+        let mut units: Vec<&'a str> = Vec::new();
+        let mut last = Last::Other;
+        for word in dictionary_words(run) {
+            let len = word.chars().count();
+            let kana = word.chars().all(|c| class(c) == Class::Hiragana);
+            let kanji = word.chars().all(|c| class(c) == Class::Han);
+            let content = word.chars().any(|c| matches!(class(c), Class::Han | Class::Katakana));
+            let (joins, next) = match last {
+                Last::Kanji(_) if kana && len <= MAX_KANA_TAIL => (true, Last::Content(len)),
+                Last::Content(n) if kana && n + len <= MAX_KANA_TAIL => {
+                    (true, Last::Content(n + len))
+                }
+                Last::Kanji(n) if kanji && n + len <= MAX_KANJI => (true, Last::Kanji(n + len)),
+                Last::Kana(n) if kana && n < KANA_GROUP => (true, Last::Kana(n + len)),
+                _ if kana => (false, Last::Kana(len)),
+                _ if kanji => (false, Last::Kanji(len)),
+                _ if content => {
+                    let tail = word.chars().filter(|&c| class(c) == Class::Hiragana).count();
+                    (false, Last::Content(tail))
+                }
+                _ => (false, Last::Other),
+            };
+            match units.last_mut() {
+                Some(unit) if joins => *unit = join(run, unit, word),
+                _ => units.push(word),
+            }
+            last = next;
+        }
+        // A short group of kana at the end joins the kana before it
+        if let (Last::Kana(n), [.., previous, final_unit]) = (last, units.as_slice()) {
+            if n < KANA_GROUP && previous.chars().all(|c| class(c) == Class::Hiragana) {
+                let joined = join(run, previous, final_unit);
+                units.pop();
+                if let Some(unit) = units.last_mut() {
+                    *unit = joined;
+                }
+            }
+        }
+        attach_punctuation(run, units)
+    }
+}
+
+impl Segmenter for Thai {
+    fn split<'a>(&self, run: &'a str) -> Vec<&'a str> {
+        attach_punctuation(run, dictionary_words(run))
+    }
+}
+
+/// Words of `run` as found by ICU's dictionary-based word segmenter, which
+/// covers Chinese, Japanese and Thai. Punctuation comes out as words of its
+/// own.
+fn dictionary_words(run: &str) -> Vec<&str> {
+    thread_local! {
+        static SEGMENTER: WordSegmenter = WordSegmenter::new_dictionary();
+    }
+    let breaks: Vec<usize> = SEGMENTER.with(|segmenter| segmenter.segment_str(run).collect());
+    breaks
+        .windows(2)
+        .map(|pair| &run[pair[0]..pair[1]])
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Which segmenter `lang` selects for `text`: `auto` picks by script,
+/// `zh`, `ja` and `th` (optionally with a region, like `zh-TW`) force one,
+/// and any other language needs none.
+pub fn for_language(lang: &str, text: &str) -> Option<Box<dyn Segmenter>> {
+    let primary = lang.split(['-', '_']).next().unwrap_or("").to_lowercase();
+    match primary.as_str() {
+        "auto" => detect(text),
+        "zh" => Some(Box::new(Chinese)),
+        "ja" => Some(Box::new(Japanese)),
+        "th" => Some(Box::new(Thai)),
+        _ => None,
+    }
+}
+
+/// Whether `lang` is `auto`, `none` or looks like a language tag.
+pub fn is_valid_language(lang: &str) -> bool {
+    let mut parts = lang.split(['-', '_']);
+    let primary = parts.next().unwrap_or("");
+    (2..=4).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Any kana means Japanese, then Han means Chinese, then Thai.
+fn detect(text: &str) -> Option<Box<dyn Segmenter>> {
+    let mut han = false;
+    let mut thai = false;
+    for c in text.chars() {
+        match class(c) {
+            Class::Hiragana | Class::Katakana => return Some(Box::new(Japanese)),
+            Class::Han => han = true,
+            Class::Thai => thai = true,
+            _ => {}
+        }
+    }
+    if han {
+        Some(Box::new(Chinese))
+    } else if thai {
+        Some(Box::new(Thai))
+    } else {
+        None
+    }
+}
+
+/// Whether a whitespace-delimited word contains anything to segment.
+pub fn needs_segmenting(word: &str) -> bool {
+    word.chars()
+        .any(|c| !matches!(class(c), Class::Other | Class::Open | Class::Close))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Han,
+    Hiragana,
+    Katakana,
+    Thai,
+    /// Opening brackets and quotes, kept with the unit after them.
+    Open,
+    /// Closing brackets, quotes and punctuation, kept with the unit before.
+    Close,
+    Other,
+}
+
+fn class(c: char) -> Class {
+    match c {
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}'
+        | '々' => Class::Han,
+        '\u{3041}'..='\u{309F}' => Class::Hiragana,
+        '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => {
+            Class::Katakana
+        }
+        '\u{0E00}'..='\u{0E7F}' => Class::Thai,
+        '「' | '『' | '（' | '【' | '〈' | '《' | '〔' | '“' | '‘' | '(' | '[' => Class::Open,
+        '。' | '、' | '，' | '．' | '！' | '？' | '；' | '：' | '」' | '』' | '）' | '】' | '〉'
+        | '》' | '〕' | '”' | '’' | '…' | ',' | '.' | '!' | '?' | ';' | ':' | ')'
+        | ']' => Class::Close,
+        _ => Class::Other,
+    }
+}
+
+/// Moves closing punctuation onto the piece before it and opening
+/// punctuation onto the piece after it.
+fn attach_punctuation<'a>(run: &'a str, pieces: Vec<&'a str>) -> Vec<&'a str> {
+    let mut merged: Vec<&'a str> = Vec::new();
+    let mut pending_open: Option<&'a str> = None;
+    for piece in pieces {
+        let piece_class = piece.chars().next().map_or(Class::Other, class);
+        let piece = match pending_open.take() {
+            Some(open) => join(run, open, piece),
+            None => piece,
+        };
+        match piece_class {
+            Class::Close if !merged.is_empty() => {
+                if let Some(last) = merged.last_mut() {
+                    *last = join(run, last, piece);
+                }
+            }
+            Class::Open => pending_open = Some(piece),
+            _ => merged.push(piece),
+        }
+    }
+    if let Some(open) = pending_open {
+        match merged.last_mut() {
+            Some(last) => *last = join(run, last, open),
+            None => merged.push(open),
+        }
+    }
+    merged
+}
+
+/// Byte offset of `piece` within `text`; `piece` must be a slice of it.
+fn offset(text: &str, piece: &str) -> usize {
+    piece.as_ptr() as usize - text.as_ptr() as usize
+}
+
+/// The slice of `run` spanning two adjacent slices of it.
+fn join<'a>(run: &'a str, first: &'a str, second: &'a str) -> &'a str {
+    let start = offset(run, first);
+    &run[start..offset(run, second) + second.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn punctuation_stays_with_its_word() {
+        assert_eq!(Chinese.split("你好吗？"), ["你好", "吗？"]);
+        assert_eq!(Chinese.split("（注意）"), ["（注意）"]);
+        assert_eq!(Japanese.split("「東京都に行きました。」"), ["「東京都に", "行きました。」"]);
+        assert_eq!(Chinese.split("hello世界"), ["hello", "世界"]);
+    }
+
+    #[test]
+    fn chinese_splits_at_word_boundaries() {
+        assert_eq!(
+            Chinese.split("我们今天去北京大学学习中文。"),
+            ["我们", "今天", "去", "北京", "大学", "学习", "中文。"]
+        );
+    }
+
+    #[test]
+    fn japanese_keeps_particles_and_endings_with_their_word() {
+        assert_eq!(
+            Japanese.split("私はコンピューターを使って、日本語を勉強しています。"),
+            ["私は", "コンピューターを", "使って、", "日本語を", "勉強しています。"]
+        );
+        assert_eq!(Japanese.split("国際連合安全保障理事会"), ["国際連合", "安全保障", "理事会"]);
+        assert_eq!(Japanese.split("ありがとうございます"), ["ありがとう", "ございます"]);
+    }
+
+    #[test]
+    fn thai_splits_at_word_boundaries() {
+        assert_eq!(
+            Thai.split("ภาษาไทยเป็นภาษาที่สวยงามมาก"),
+            ["ภาษา", "ไทย", "เป็น", "ภาษา", "ที่", "สวยงาม", "มาก"]
+        );
+        assert_eq!(Thai.split("สวัสดีครับ"), ["สวัสดี", "ครับ"]);
+    }
+
+    #[test]
+    fn pieces_cover_the_whole_run() {
+        let runs = ["我们今天去北京大学学习中文。", "「東京都に行きました。」", "ภาษาไทยดี"];
+        for (segmenter, run) in [&Chinese as &dyn Segmenter, &Japanese, &Thai].into_iter().zip(runs) {
+            let pieces = segmenter.split(run);
+            assert!(pieces.iter().all(|piece| !piece.is_empty()));
+            assert_eq!(pieces.concat(), run);
+        }
+    }
+
+    #[test]
+    fn language_selects_a_segmenter() {
+        assert!(for_language("auto", "日本語です").is_some());
+        assert!(for_language("zh-TW", "abc").is_some());
+        assert!(for_language("none", "日本語").is_none());
+        assert!(for_language("auto", "plain English").is_none());
+    }
+}
@@ -1,63 +1,254 @@
-use std::time::{Duration, Instant};
+use crate::{
+    document::{Chapter, Emphasis},
+    search,
+    timing::Timing,
+    tokenize::{self, Token},
+};
+use std::{
+    borrow::Cow,
+    time::{Duration, Instant},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Bounds for live speed adjustment.
+pub const MIN_WPM: u64 = 50;
+pub const MAX_WPM: u64 = 2000;
 
 #[derive(Debug)]
 pub struct AppState<'a> {
-    words: Vec<&'a str>,
+    words: Vec<Token<'a>>,
+    chapters: &'a [Chapter],
+    emphasis: &'a [Emphasis],
     current_word: usize,
     paused: bool,
     wpm: u64,
+    timing: Timing,
+    /// Display width past which words are shown in parts (0 = never split).
+    /// Also the widest a chunk of several words may be.
+    max_word_width: usize,
+    /// Most words shown together in one flash.
+    chunk_size: usize,
+    /// Which part of the current word is on screen.
+    part: usize,
+    /// Words shown since speed last ramped up from a standstill.
+    ramp_position: usize,
+    /// When the current word's display time runs out.
     next_tick: Instant,
 }
 
 impl<'a> AppState<'a> {
     pub fn new(content: &'a str, wpm: u64) -> Self {
-        let words: Vec<&'a str> = content.split_whitespace().collect();
-        let delay = Duration::from_secs_f64(60.0 / wpm as f64);
+        let words = tokenize::tokenize(content);
 
-        Self {
+        let mut state = Self {
             words,
+            chapters: &[],
+            emphasis: &[],
             current_word: 0,
             paused: false,
             wpm,
-            next_tick: Instant::now() + delay,
+            timing: Timing::default(),
+            max_word_width: 0,
+            chunk_size: 1,
+            part: 0,
+            ramp_position: 0,
+            next_tick: Instant::now(),
+        };
+        state.reset_tick();
+        state
+    }
+
+    /// Replaces the default per-word timing model.
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self.reset_tick();
+        self
+    }
+
+    /// Shows words wider than `width` columns in parts, one after another.
+    pub fn with_max_word_width(mut self, width: usize) -> Self {
+        self.max_word_width = width;
+        self.reset_tick();
+        self
+    }
+
+    /// Shows up to `size` words at a time, as phrases that stop at
+    /// punctuation and fit the maximum word width.
+    pub fn with_chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self.reset_tick();
+        self
+    }
+
+    /// Display time of the current chunk at the current speed: the sum of its
+    /// words' times, stretched while ramping up. A word shown in parts gets
+    /// one word's reading time per part, shared out by width, and any pause
+    /// for its punctuation on the last part only.
+    fn current_duration(&self) -> Duration {
+        let Some(word) = self.words.get(self.current_word) else {
+            return Duration::ZERO;
+        };
+        let parts = self.current_parts();
+        let duration = match parts.get(self.part).filter(|_| parts.len() > 1) {
+            Some(part) => {
+                let next = self.words.get(self.current_word + 1);
+                let (reading, pause) = self.timing.reading_and_pause(word, next, self.wpm);
+                let share = part.width() as f64 / word.text.width() as f64;
+                let pause = if self.part + 1 == parts.len() { pause } else { Duration::ZERO };
+                reading.mul_f64(parts.len() as f64 * share) + pause
+            }
+            None => (self.current_word..self.chunk_end())
+                .map(|i| {
+                    let next = self.words.get(i + 1);
+                    self.timing.word_duration(&self.words[i], next, self.wpm)
+                })
+                .sum(),
+        };
+        duration.div_f64(self.timing.ramp_factor(self.ramp_position))
+    }
+
+    /// End (exclusive) of the chunk starting at the current word. A chunk
+    /// grows word by word up to the chunk size, but not past the end of a
+    /// clause or sentence, beyond the maximum word width or into words with
+    /// other emphasis, since a chunk is drawn in one style.
+    fn chunk_end(&self) -> usize {
+        let start = self.current_word;
+        let Some(first) = self.words.get(start) else {
+            return start;
+        };
+        let mut end = start + 1;
+        let mut width = first.text.width();
+        while let Some(next) = self.words.get(end).filter(|_| end - start < self.chunk_size) {
+            let previous = self.words[end - 1].text;
+            if next.sentence_start
+                || tokenize::ends_sentence(previous)
+                || tokenize::ends_clause(previous)
+                || self.emphasis.get(end) != self.emphasis.get(end - 1)
+            {
+                break;
+            }
+            width += 1 + next.text.width();
+            if self.max_word_width > 0 && width > self.max_word_width {
+                break;
+            }
+            end += 1;
         }
+        end
+    }
+
+    /// Number of words in the chunk on screen.
+    pub fn chunk_len(&self) -> usize {
+        self.chunk_end() - self.current_word
+    }
+
+    /// The current word cut to the maximum word width.
+    fn current_parts(&self) -> Vec<&'a str> {
+        self.words
+            .get(self.current_word)
+            .map(|token| tokenize::split_long_word(token.text, self.max_word_width))
+            .unwrap_or_default()
+    }
+
+    /// Restarts the warm-up so the next words are shown more slowly.
+    fn restart_ramp(&mut self) {
+        self.ramp_position = 0;
+        self.reset_tick();
+    }
+
+    /// Gives the current word its full display window starting now.
+    fn reset_tick(&mut self) {
+        self.next_tick = Instant::now() + self.current_duration();
+    }
+
+    /// Attaches chapter boundaries so the current chapter can be reported.
+    pub fn with_chapters(mut self, chapters: &'a [Chapter]) -> Self {
+        self.chapters = chapters;
+        self
+    }
+
+    /// Attaches per-word emphasis recovered from the source markup.
+    pub fn with_emphasis(mut self, emphasis: &'a [Emphasis]) -> Self {
+        self.emphasis = emphasis;
+        self
+    }
+
+    /// Emphasis of the current word, shared by the rest of its chunk (plain
+    /// when the source had none).
+    pub fn current_emphasis(&self) -> Emphasis {
+        self.emphasis
+            .get(self.current_word)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The chapter containing the current word, if the document has chapters.
+    pub fn current_chapter(&self) -> Option<&Chapter> {
+        self.chapters
+            .iter()
+            .rev()
+            .find(|chapter| chapter.start_word <= self.current_word)
     }
 
     pub fn current_word(&self) -> Option<&str> {
-        self.words.get(self.current_word).copied()
+        self.words.get(self.current_word).map(|token| token.text)
+    }
+
+    /// What is on screen, and whether more parts of it follow: the words of
+    /// the current chunk, or a part of the current word when it is wider than
+    /// the maximum width.
+    pub fn current_part(&self) -> Option<(Cow<'a, str>, bool)> {
+        let end = self.chunk_end();
+        if end - self.current_word > 1 {
+            let words: Vec<&str> = self.words[self.current_word..end]
+                .iter()
+                .map(|token| token.text)
+                .collect();
+            return Some((Cow::Owned(words.join(" ")), false));
+        }
+        let parts = self.current_parts();
+        let part = parts.get(self.part)?;
+        Some((Cow::Borrowed(part), self.part + 1 < parts.len()))
     }
 
     pub fn toggle_pause(&mut self) {
         self.paused = !self.paused;
+        if !self.paused {
+            self.restart_ramp();
+        }
     }
 
     pub fn should_advance(&self) -> bool {
         Instant::now() >= self.next_tick && !self.paused
     }
 
+    /// Moves on to the next part of a word shown in parts. False when the
+    /// last part is already on screen.
+    pub fn advance_part(&mut self) -> bool {
+        if self.part + 1 >= self.current_parts().len() {
+            return false;
+        }
+        self.part += 1;
+        self.reset_tick();
+        true
+    }
+
+    /// Moves past the chunk on screen. False at the end of the text.
     pub fn advance_word(&mut self) -> bool {
-        self.current_word += 1;
+        let next = self.chunk_end().max(self.current_word + 1);
+        let shown = next - self.current_word;
+        self.current_word = next;
+        self.part = 0;
         if self.current_word >= self.words.len() {
             return false; // No more words
         }
-        let delay = Duration::from_secs_f64(60.0 / self.wpm as f64);
-        self.next_tick = Instant::now() + delay;
+        // The warm-up counts words, however many were shown at once
+        self.ramp_position = self.ramp_position.saturating_add(shown);
+        self.reset_tick();
         true // More words remaining
     }
 
     pub fn get_timeout(&self) -> Duration {
-        let last_char = self.current_word()
-            .and_then(|word| word.chars().last())
-            .unwrap_or(' ');
-
-        // Only pause on sentence-ending punctuation
-        let punctuation_delay = if matches!(last_char, '.' | '!' | '?' | ';') {
-            Duration::from_secs_f64(0.5)
-        } else {
-            Duration::from_secs(0)
-        };
-
-        self.next_tick.saturating_duration_since(Instant::now()) + punctuation_delay
+        self.next_tick.saturating_duration_since(Instant::now())
     }
 
     pub fn current_word_index(&self) -> usize {
@@ -72,21 +263,243 @@ impl<'a> AppState<'a> {
         self.paused
     }
 
-    /// Returns up to `count` words following the current word.
+    pub fn wpm(&self) -> u64 {
+        self.wpm
+    }
+
+    /// Change reading speed by `delta` WPM, clamped to `MIN_WPM..=MAX_WPM`.
+    /// The word on screen keeps its current deadline; the new speed applies
+    /// from the next word.
+    pub fn adjust_wpm(&mut self, delta: i64) {
+        self.wpm = (self.wpm as i64 + delta).clamp(MIN_WPM as i64, MAX_WPM as i64) as u64;
+    }
+
+    /// Returns up to `count` words following the current chunk.
     pub fn peek_words(&self, count: usize) -> Vec<&str> {
-        let start = self.current_word + 1;
-        self.words[start..].iter().take(count).copied().collect()
+        let start = self.chunk_end().max(self.current_word + 1).min(self.words.len());
+        self.words[start..].iter().take(count).map(|token| token.text).collect()
+    }
+
+    /// Words of the paragraph (or just the sentence, with `sentence_only`)
+    /// around the current word, at most `radius` words on each side. Returns
+    /// the words and the position of the current word among them.
+    pub fn context_words(&self, sentence_only: bool, radius: usize) -> (Vec<&str>, usize) {
+        let is_boundary = |token: &Token| {
+            if sentence_only {
+                token.sentence_start
+            } else {
+                token.paragraph_start
+            }
+        };
+        let Some(current) = self.words.get(self.current_word) else {
+            return (Vec::new(), 0);
+        };
+        let block_start = if is_boundary(current) {
+            self.current_word
+        } else {
+            self.prev_boundary(is_boundary)
+        };
+        let block_end = self.next_boundary(is_boundary).unwrap_or(self.words.len());
+
+        let start = block_start.max(self.current_word.saturating_sub(radius));
+        let end = block_end.min(self.current_word + radius + 1);
+        let words = self.words[start..end].iter().map(|token| token.text).collect();
+        (words, self.current_word - start)
+    }
+
+    /// Start indices of every occurrence of `query`, matched case-insensitively
+    /// and ignoring punctuation. A multi-word query matches a phrase of
+    /// consecutive words; every query word must match its word exactly except
+    /// the last, which may be the start of one (so `war and pea` finds
+    /// "War and Peace").
+    pub fn find_matches(&self, query: &str) -> Vec<usize> {
+        let terms: Vec<String> = query.split_whitespace().map(search::normalize).collect();
+        if terms.is_empty() || terms.iter().all(|term| term.is_empty()) {
+            return Vec::new();
+        }
+        let normalized: Vec<String> = self
+            .words
+            .iter()
+            .map(|token| search::normalize(token.text))
+            .collect();
+        let last = terms.len() - 1;
+        normalized
+            .windows(terms.len())
+            .enumerate()
+            .filter(|(_, window)| {
+                window[..last] == terms[..last] && window[last].starts_with(terms[last].as_str())
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Jump to an absolute word index (clamped to word bounds).
+    pub fn jump_to(&mut self, index: usize) {
+        self.seek_word(index as isize - self.current_word as isize);
     }
 
     /// Jump forward or backward by `delta` words (clamped to word bounds).
-    /// Resets the tick timer so the landed-on word gets a full display window.
+    /// Restarts the ramp-up so the landed-on word gets a full, slowed display window.
     pub fn seek_word(&mut self, delta: isize) {
         let new_index = (self.current_word as isize + delta)
             .max(0)
             .min((self.words.len() as isize).saturating_sub(1)) as usize;
         self.current_word = new_index;
-        let delay = Duration::from_secs_f64(60.0 / self.wpm as f64);
-        self.next_tick = Instant::now() + delay;
+        self.part = 0;
+        self.restart_ramp();
+    }
+
+    /// Jump to the start of the current sentence, or to the previous sentence
+    /// when already on a sentence start.
+    pub fn prev_sentence(&mut self) {
+        self.jump_to(self.prev_boundary(|token| token.sentence_start));
+    }
+
+    /// Jump to the start of the next sentence (stays put in the last sentence).
+    pub fn next_sentence(&mut self) {
+        if let Some(index) = self.next_boundary(|token| token.sentence_start) {
+            self.jump_to(index);
+        }
+    }
+
+    /// Jump to the start of the current paragraph, or to the previous
+    /// paragraph when already on a paragraph start.
+    pub fn prev_paragraph(&mut self) {
+        self.jump_to(self.prev_boundary(|token| token.paragraph_start));
+    }
+
+    /// Jump to the start of the next paragraph (stays put in the last paragraph).
+    pub fn next_paragraph(&mut self) {
+        if let Some(index) = self.next_boundary(|token| token.paragraph_start) {
+            self.jump_to(index);
+        }
+    }
+
+    /// Index of the last word before the current one matching `is_boundary`.
+    fn prev_boundary(&self, is_boundary: impl Fn(&Token) -> bool) -> usize {
+        self.words[..self.current_word]
+            .iter()
+            .rposition(is_boundary)
+            .unwrap_or(0)
+    }
+
+    /// Index of the first word after the current one matching `is_boundary`.
+    fn next_boundary(&self, is_boundary: impl Fn(&Token) -> bool) -> Option<usize> {
+        let start = self.current_word + 1;
+        self.words
+            .get(start..)?
+            .iter()
+            .position(is_boundary)
+            .map(|offset| start + offset)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "One two. Three four five. Six seven!\n\nEight nine. Ten.";
+
+    #[test]
+    fn sentence_navigation_goes_to_sentence_starts() {
+        let mut state = AppState::new(TEXT, 300);
+        state.jump_to(4);
+        // Mid-sentence goes back to the start of that sentence, then one further
+        state.prev_sentence();
+        assert_eq!(state.current_word_index(), 2);
+        state.prev_sentence();
+        assert_eq!(state.current_word_index(), 0);
+        state.prev_sentence();
+        assert_eq!(state.current_word_index(), 0);
+
+        state.next_sentence();
+        assert_eq!(state.current_word_index(), 2);
+        state.jump_to(9);
+        state.next_sentence();
+        assert_eq!(state.current_word_index(), 9);
+    }
+
+    #[test]
+    fn paragraph_navigation_goes_to_paragraph_starts() {
+        let mut state = AppState::new(TEXT, 300);
+        state.next_paragraph();
+        assert_eq!(state.current_word_index(), 7);
+        state.next_paragraph();
+        assert_eq!(state.current_word_index(), 7);
+        state.jump_to(8);
+        state.prev_paragraph();
+        assert_eq!(state.current_word_index(), 7);
+        state.prev_paragraph();
+        assert_eq!(state.current_word_index(), 0);
+    }
+
+    #[test]
+    fn rewinding_while_paused_stays_paused_and_restarts_the_warm_up() {
+        let mut state = AppState::new(TEXT, 300);
+        state.jump_to(3);
+        state.advance_word();
+        assert_eq!(state.ramp_position, 1);
+        state.toggle_pause();
+        state.prev_sentence();
+        assert!(state.is_paused());
+        assert_eq!(state.current_word_index(), 2);
+        assert_eq!(state.ramp_position, 0);
+    }
+
+    #[test]
+    fn find_matches_requires_whole_words_except_the_last() {
+        let state = AppState::new("War and Peace. A warden, and peaceful wars; war and pea", 300);
+        // "warden, and peaceful" contains each term but is not the phrase
+        assert_eq!(state.find_matches("war and pea"), vec![0, 8]);
+        assert_eq!(state.find_matches("war and peace"), vec![0]);
+        assert_eq!(state.find_matches("WAR"), vec![0, 4, 7, 8]);
+        assert!(state.find_matches("ar and").is_empty());
+        assert!(state.find_matches("den").is_empty());
+    }
+
+    #[test]
+    fn chunks_end_where_emphasis_changes() {
+        let plain = Emphasis::default();
+        let bold = Emphasis {
+            bold: true,
+            italic: false,
+        };
+        let emphasis = [plain, plain, bold, bold, bold, plain];
+        let mut state = AppState::new("one two three four five six", 300)
+            .with_emphasis(&emphasis)
+            .with_chunk_size(3);
+        let mut chunks = vec![state.chunk_len()];
+        while state.advance_word() {
+            chunks.push(state.chunk_len());
+        }
+        assert_eq!(chunks, [2, 3, 1]);
+    }
+
+    #[test]
+    fn warm_up_counts_every_word_of_a_chunk() {
+        let mut state = AppState::new("one two three four five six", 300).with_chunk_size(3);
+        state.advance_word();
+        assert_eq!(state.ramp_position, 3);
+    }
+
+    #[test]
+    fn punctuation_pause_goes_on_the_last_part_only() {
+        let timing = Timing {
+            ramp_words: 0,
+            ..Timing::default()
+        };
+        // At 60 wpm a long word is read in 1.3 s per part, shared out by
+        // width, and ending a sentence adds as much again as a pause
+        let mut state = AppState::new("abcdefghij. Next words follow", 60)
+            .with_timing(timing)
+            .with_max_word_width(6);
+        let parts = state.current_parts();
+        assert_eq!(parts, ["abc", "defg", "hij."]);
+        let expected = |part: &str| 1.3 * 3.0 * part.len() as f64 / 11.0;
+        for (i, part) in parts.iter().enumerate() {
+            let pause = if i == 2 { 1.3 } else { 0.0 };
+            assert!((state.current_duration().as_secs_f64() - expected(part) - pause).abs() < 1e-9);
+            state.advance_part();
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::dirs;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Counters gathered while reading.
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    /// Words that were shown for their full display time.
+    pub words_read: usize,
+    /// Wall-clock time in the reader, pauses included.
+    pub elapsed: Duration,
+    pub pauses: usize,
+    /// Backward jumps: rewinds and previous sentence/paragraph.
+    pub rewinds: usize,
+}
+
+impl SessionStats {
+    /// Words read per minute of wall-clock time, pauses included.
+    pub fn effective_wpm(&self) -> f64 {
+        let minutes = self.elapsed.as_secs_f64() / 60.0;
+        if minutes > 0.0 {
+            self.words_read as f64 / minutes
+        } else {
+            0.0
+        }
+    }
+}
+
+impl SessionStats {
+    /// Label/value pairs shown on the end-of-reading summary.
+    pub fn summary_rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Words read", self.words_read.to_string()),
+            ("Time", format_duration(self.elapsed.as_secs_f64())),
+            ("Speed", format!("{:.0} wpm (pauses included)", self.effective_wpm())),
+            ("Pauses", self.pauses.to_string()),
+            ("Rewinds", self.rewinds.to_string()),
+        ]
+    }
+}
+
+/// One finished session as stored in the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Unix time the session ended.
+    pub timestamp: u64,
+    /// Content hash identifying the document (see `position::document_key`).
+    pub document: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub total_words: usize,
+    /// Word the session ended on.
+    pub position: usize,
+    pub finished: bool,
+    pub words_read: usize,
+    pub seconds: f64,
+    pub effective_wpm: f64,
+    pub pauses: usize,
+    pub rewinds: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    #[serde(default)]
+    sessions: Vec<SessionRecord>,
+}
+
+impl SessionRecord {
+    pub fn new(
+        document: String,
+        path: Option<String>,
+        total_words: usize,
+        position: usize,
+        finished: bool,
+        stats: &SessionStats,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            timestamp,
+            document,
+            path,
+            total_words,
+            position,
+            finished,
+            words_read: stats.words_read,
+            seconds: stats.elapsed.as_secs_f64(),
+            effective_wpm: stats.effective_wpm(),
+            pauses: stats.pauses,
+            rewinds: stats.rewinds,
+        }
+    }
+}
+
+pub fn history_path() -> Result<PathBuf> {
+    Ok(dirs::data_dir()?.join("history.toml"))
+}
+
+/// Appends a session to the history file. Each record is written as its own
+/// `[[sessions]]` table, so the file never has to be rewritten.
+pub fn record(session: SessionRecord) -> Result<()> {
+    let path = history_path()?;
+    let with_path = |e| dirs::with_path(&path, e);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(with_path)?;
+    }
+    // Carry pre-XDG history over before the first append to the new file
+    let legacy = dirs::readable(path.clone());
+    if legacy != path {
+        fs::copy(&legacy, &path).map_err(with_path)?;
+    }
+    let entry = toml::to_string(&History {
+        sessions: vec![session],
+    })?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(with_path)?;
+    writeln!(file, "{}", entry)?;
+    Ok(())
+}
+
+fn load() -> Result<Vec<SessionRecord>> {
+    let path = dirs::readable(history_path()?);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let history: History = toml::from_str(&fs::read_to_string(&path)?)?;
+    Ok(history.sessions)
+}
+
+/// Prints totals, speed per day and per-document progress for `spr stats`.
+pub fn print_summary() -> Result<()> {
+    let sessions = load()?;
+    if sessions.is_empty() {
+        println!("No reading sessions recorded yet");
+        return Ok(());
+    }
+
+    let words: usize = sessions.iter().map(|s| s.words_read).sum();
+    let seconds: f64 = sessions.iter().map(|s| s.seconds).sum();
+    println!("Sessions      {}", sessions.len());
+    println!("Words read    {}", words);
+    println!("Time spent    {}", format_duration(seconds));
+    println!("Average speed {:.0} wpm", wpm(words, seconds));
+    println!(
+        "Pauses        {}   Rewinds {}",
+        sessions.iter().map(|s| s.pauses).sum::<usize>(),
+        sessions.iter().map(|s| s.rewinds).sum::<usize>()
+    );
+
+    // Speed over time, one line per day read
+    let mut days: BTreeMap<u64, (usize, f64, usize)> = BTreeMap::new();
+    for session in &sessions {
+        let day = days.entry(session.timestamp / 86_400).or_default();
+        day.0 += session.words_read;
+        day.1 += session.seconds;
+        day.2 += 1;
+    }
+    println!();
+    println!("{:<12}{:>10}{:>10}{:>12}", "Date", "Sessions", "Words", "Avg wpm");
+    for (day, (words, seconds, count)) in &days {
+        println!(
+            "{:<12}{:>10}{:>10}{:>12.0}",
+            format_date(*day),
+            count,
+            words,
+            wpm(*words, *seconds)
+        );
+    }
+
+    // Progress per document, from its most recent session
+    let mut documents: BTreeMap<&str, (&SessionRecord, usize)> = BTreeMap::new();
+    for session in &sessions {
+        let entry = documents
+            .entry(session.document.as_str())
+            .or_insert((session, 0));
+        if session.timestamp >= entry.0.timestamp {
+            entry.0 = session;
+        }
+        entry.1 += session.words_read;
+    }
+    println!();
+    println!("{:<8}{:>10}  Document", "Progress", "Read");
+    for (key, (latest, words)) in &documents {
+        let progress = if latest.finished {
+            100
+        } else {
+            (latest.position + 1) * 100 / latest.total_words.max(1)
+        };
+        let name = latest.path.as_deref().unwrap_or(key);
+        println!("{:>7}%{:>10}  {}", progress, words, name);
+    }
+
+    Ok(())
+}
+
+fn wpm(words: usize, seconds: f64) -> f64 {
+    if seconds > 0.0 {
+        words as f64 / (seconds / 60.0)
+    } else {
+        0.0
+    }
+}
+
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    match (total / 3600, total / 60 % 60, total % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Formats days since the Unix epoch as `YYYY-MM-DD` (UTC), using the
+/// civil-from-days algorithm to avoid a date-time dependency.
+fn format_date(days: u64) -> String {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
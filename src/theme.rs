@@ -0,0 +1,209 @@
+use crate::{color::ColorDepth, config, dirs};
+use color_eyre::Result;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::{fs, path::PathBuf};
+
+/// Built-in themes, also shipped as files in `themes/` to copy from.
+const BUILTIN: [(&str, &str); 5] = [
+    ("default", include_str!("../themes/default.toml")),
+    ("solarized", include_str!("../themes/solarized.toml")),
+    ("gruvbox", include_str!("../themes/gruvbox.toml")),
+    ("high-contrast", include_str!("../themes/high-contrast.toml")),
+    ("monochrome", include_str!("../themes/monochrome.toml")),
+];
+
+/// Per-element colors as written in a theme file or config. Unset entries
+/// fall through to the layer below.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Palette {
+    pub border_color: Option<String>,
+    pub progress_bar_color: Option<String>,
+    pub focus_color: Option<String>,
+    pub word_color: Option<String>,
+    pub preview_color: Option<String>,
+    pub context_color: Option<String>,
+    pub paused_color: Option<String>,
+    pub help_key_color: Option<String>,
+}
+
+impl Palette {
+    /// Every color key, in field order.
+    pub const KEYS: [&'static str; 8] = [
+        "border_color",
+        "progress_bar_color",
+        "focus_color",
+        "word_color",
+        "preview_color",
+        "context_color",
+        "paused_color",
+        "help_key_color",
+    ];
+
+    /// `(key, value)` for every set entry. An empty value counts as unset.
+    pub fn entries(&self) -> Vec<(&'static str, &str)> {
+        let values = [
+            &self.border_color,
+            &self.progress_bar_color,
+            &self.focus_color,
+            &self.word_color,
+            &self.preview_color,
+            &self.context_color,
+            &self.paused_color,
+            &self.help_key_color,
+        ];
+        Self::KEYS
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| Some((key, value.as_deref().filter(|v| !v.is_empty())?)))
+        .collect()
+    }
+
+    /// Replaces entries with those set in `other`.
+    pub fn overlay(&mut self, other: &Palette) {
+        let layers = [
+            (&mut self.border_color, &other.border_color),
+            (&mut self.progress_bar_color, &other.progress_bar_color),
+            (&mut self.focus_color, &other.focus_color),
+            (&mut self.word_color, &other.word_color),
+            (&mut self.preview_color, &other.preview_color),
+            (&mut self.context_color, &other.context_color),
+            (&mut self.paused_color, &other.paused_color),
+            (&mut self.help_key_color, &other.help_key_color),
+        ];
+        for (mine, theirs) in layers {
+            if let Some(value) = theirs.as_ref().filter(|v| !v.is_empty()) {
+                *mine = Some(value.clone());
+            }
+        }
+    }
+}
+
+/// Where user themes live: `<config dir>/themes/<name>.toml`.
+pub fn themes_dir() -> Result<PathBuf> {
+    Ok(dirs::config_dir()?.join("themes"))
+}
+
+/// Names of the built-in themes.
+pub fn builtin_names() -> Vec<&'static str> {
+    BUILTIN.iter().map(|(name, _)| *name).collect()
+}
+
+/// Loads a theme by name, preferring a user theme over a built-in one of the
+/// same name. Errors are ready to print, located in the theme file where
+/// possible.
+pub fn load(name: &str) -> std::result::Result<Palette, String> {
+    let user_path = themes_dir()
+        .ok()
+        .map(|dir| dir.join(format!("{}.toml", name)))
+        .filter(|path| path.exists());
+
+    let (path, contents) = match user_path {
+        Some(path) => {
+            let contents =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            (path, contents)
+        }
+        None => {
+            let (_, contents) = BUILTIN.iter().find(|(builtin, _)| *builtin == name).ok_or_else(|| {
+                let dir = themes_dir()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_else(|_| "the themes directory".to_string());
+                format!(
+                    "unknown theme `{}` (built-in themes: {}; user themes go in {})",
+                    name,
+                    builtin_names().join(", "),
+                    dir
+                )
+            })?;
+            (PathBuf::from(format!("<built-in theme {}>", name)), contents.to_string())
+        }
+    };
+
+    let palette: Palette = toml::from_str(&contents)
+        .map_err(|e| config::locate(&path, &contents, e.span(), e.message()))?;
+    let document = toml_edit::ImDocument::parse(contents.as_str()).ok();
+    for (key, value) in palette.entries() {
+        if config::parse_color(value).is_none() {
+            let span = document
+                .as_ref()
+                .and_then(|document| config::key_span(document.as_table(), key));
+            return Err(config::locate(
+                &path,
+                &contents,
+                span,
+                &config::invalid_color(key, value),
+            ));
+        }
+    }
+    Ok(palette)
+}
+
+/// Resolved colors for each UI element.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub border: Color,
+    pub progress_bar: Color,
+    /// The highlighted focus letter.
+    pub focus: Color,
+    /// The current word.
+    pub word: Color,
+    /// Preview words and other secondary text: the status line, hints and
+    /// the unfilled part of the progress bar.
+    pub preview: Color,
+    /// Words around the current one while paused, and labels.
+    pub context: Color,
+    /// The progress bar while paused.
+    pub paused: Color,
+    /// Key names in the help popup.
+    pub help_key: Color,
+    /// What the terminal can show. Drawn colors are adapted to it.
+    pub depth: ColorDepth,
+}
+
+impl Theme {
+    /// Parses a layered palette. The focus letter takes the border color when
+    /// the palette doesn't set one.
+    pub fn from_palette(palette: &Palette, depth: ColorDepth) -> Self {
+        let color = |value: &Option<String>, fallback: Color| {
+            value
+                .as_deref()
+                .and_then(config::parse_color)
+                .unwrap_or(fallback)
+        };
+        let border = color(&palette.border_color, Color::Cyan);
+        Self {
+            border,
+            progress_bar: color(&palette.progress_bar_color, Color::Cyan),
+            focus: color(&palette.focus_color, border),
+            word: color(&palette.word_color, Color::Reset),
+            preview: color(&palette.preview_color, Color::DarkGray),
+            context: color(&palette.context_color, Color::Gray),
+            paused: color(&palette.paused_color, Color::Rgb(255, 165, 0)),
+            help_key: color(&palette.help_key_color, Color::Yellow),
+            depth,
+        }
+    }
+
+    /// The focus letter: bold, and also underlined when there is no color to
+    /// set it apart.
+    pub fn focus_style(&self) -> Style {
+        let style = Style::default().fg(self.focus).add_modifier(Modifier::BOLD);
+        if self.depth == ColorDepth::NoColor {
+            style.add_modifier(Modifier::UNDERLINED)
+        } else {
+            style
+        }
+    }
+
+    /// Preview words and other secondary text, dimmed when there is no color.
+    pub fn preview_style(&self) -> Style {
+        let style = Style::default().fg(self.preview);
+        if self.depth == ColorDepth::NoColor {
+            style.add_modifier(Modifier::DIM)
+        } else {
+            style
+        }
+    }
+}
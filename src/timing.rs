@@ -0,0 +1,243 @@
+use crate::tokenize::{self, Token};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Upper bound for any timing multiplier.
+const MAX_MULTIPLIER: f64 = 10.0;
+/// Upper bounds for `long_word_length` and `ramp_words`.
+const MAX_LONG_WORD_LENGTH: usize = 50;
+const MAX_RAMP_WORDS: usize = 100;
+
+/// Multipliers applied to the base per-word delay (`60 / wpm` seconds).
+///
+/// Punctuation multipliers do not stack: a word ending a paragraph gets
+/// `paragraph_end` only, not also `sentence_end`. Length and number
+/// multipliers stack on top of whichever punctuation multiplier applies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timing {
+    /// Words ending in `,` `;` `:` or a dash.
+    pub comma: f64,
+    /// Words ending in `.` `!` `?` or `…`.
+    pub sentence_end: f64,
+    /// The last word before a blank line, and the final word.
+    pub paragraph_end: f64,
+    /// Words longer than `long_word_length` characters.
+    pub long_word: f64,
+    pub long_word_length: usize,
+    /// Words containing a digit.
+    pub number: f64,
+    /// Fraction of the target WPM used for the first word after the session
+    /// starts, a pause ends or a seek lands.
+    pub ramp_start: f64,
+    /// Words over which speed climbs from `ramp_start` to full (0 disables).
+    pub ramp_words: usize,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            comma: 1.5,
+            sentence_end: 2.0,
+            paragraph_end: 2.5,
+            long_word: 1.3,
+            long_word_length: 8,
+            number: 1.3,
+            ramp_start: 0.5,
+            ramp_words: 5,
+        }
+    }
+}
+
+impl Timing {
+    /// How long `word` should stay on screen at `wpm`. `next` is the word
+    /// that follows, used to detect paragraph ends.
+    pub fn word_duration(&self, word: &Token, next: Option<&Token>, wpm: u64) -> Duration {
+        let (reading, pause) = self.reading_and_pause(word, next, wpm);
+        reading + pause
+    }
+
+    /// `word_duration` in two: the time for reading the word itself, and the
+    /// pause its punctuation adds after it.
+    pub fn reading_and_pause(
+        &self,
+        word: &Token,
+        next: Option<&Token>,
+        wpm: u64,
+    ) -> (Duration, Duration) {
+        let base = 60.0 / wpm.max(1) as f64;
+        let reading = base * self.content_multiplier(word.text);
+        let total = reading * self.punctuation_multiplier(word, next);
+        // A multiplier below 1 shortens the word instead of pausing after it
+        let reading = reading.min(total);
+        (Duration::from_secs_f64(reading), Duration::from_secs_f64(total - reading))
+    }
+
+    /// Speed factor for the `position`-th word (0-based) since the last
+    /// ramp start, climbing linearly from `ramp_start` to 1.0.
+    pub fn ramp_factor(&self, position: usize) -> f64 {
+        if position >= self.ramp_words {
+            return 1.0;
+        }
+        let start = self.ramp_start.clamp(0.05, 1.0);
+        start + (1.0 - start) * position as f64 / self.ramp_words as f64
+    }
+
+    /// Out-of-range values as `(key, message)` pairs, keys relative to the
+    /// config root.
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        let multipliers = [
+            ("comma", self.comma),
+            ("sentence_end", self.sentence_end),
+            ("paragraph_end", self.paragraph_end),
+            ("long_word", self.long_word),
+            ("number", self.number),
+        ];
+        for (name, value) in multipliers {
+            if !(value > 0.0 && value <= MAX_MULTIPLIER) {
+                problems.push((
+                    format!("timing.{}", name),
+                    format!(
+                        "`timing.{}` must be greater than 0 and at most {}, got {}",
+                        name, MAX_MULTIPLIER, value
+                    ),
+                ));
+            }
+        }
+        if !(self.ramp_start > 0.0 && self.ramp_start <= 1.0) {
+            problems.push((
+                "timing.ramp_start".to_string(),
+                format!(
+                    "`timing.ramp_start` must be greater than 0 and at most 1, got {}",
+                    self.ramp_start
+                ),
+            ));
+        }
+        if !(1..=MAX_LONG_WORD_LENGTH).contains(&self.long_word_length) {
+            problems.push((
+                "timing.long_word_length".to_string(),
+                format!(
+                    "`timing.long_word_length` must be between 1 and {}, got {}",
+                    MAX_LONG_WORD_LENGTH, self.long_word_length
+                ),
+            ));
+        }
+        if self.ramp_words > MAX_RAMP_WORDS {
+            problems.push((
+                "timing.ramp_words".to_string(),
+                format!(
+                    "`timing.ramp_words` must be at most {}, got {}",
+                    MAX_RAMP_WORDS, self.ramp_words
+                ),
+            ));
+        }
+        problems
+    }
+
+    fn punctuation_multiplier(&self, word: &Token, next: Option<&Token>) -> f64 {
+        if next.is_none_or(|next| next.paragraph_start) {
+            self.paragraph_end
+        } else if tokenize::ends_sentence(word.text) {
+            self.sentence_end
+        } else if tokenize::ends_clause(word.text) {
+            self.comma
+        } else {
+            1.0
+        }
+    }
+
+    fn content_multiplier(&self, text: &str) -> f64 {
+        let mut multiplier = 1.0;
+        if text.chars().count() > self.long_word_length {
+            multiplier *= self.long_word;
+        }
+        if text.chars().any(|c| c.is_ascii_digit()) {
+            multiplier *= self.number;
+        }
+        multiplier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Multiplier applied to each word of `content` at 60 wpm, where the
+    /// base delay is one second.
+    fn multipliers(timing: &Timing, content: &str) -> Vec<f64> {
+        let tokens = tokenize::tokenize(content);
+        (0..tokens.len())
+            .map(|i| timing.word_duration(&tokens[i], tokens.get(i + 1), 60).as_secs_f64())
+            .collect()
+    }
+
+    #[test]
+    fn punctuation_multipliers_do_not_stack() {
+        let timing = Timing::default();
+        assert_eq!(
+            multipliers(&timing, "Well, then. Fine!\n\nNext \"one.\" end"),
+            [1.5, 2.0, 2.5, 1.0, 2.0, 2.5]
+        );
+    }
+
+    #[test]
+    fn length_and_number_multipliers_stack_on_punctuation() {
+        let timing = Timing {
+            long_word_length: 5,
+            ..Timing::default()
+        };
+        let [long, number, both, last] = multipliers(&timing, "lengthy, 42 abc123def. x")[..] else {
+            panic!("expected four words");
+        };
+        assert!((long - 1.3 * 1.5).abs() < 1e-9);
+        assert!((number - 1.3).abs() < 1e-9);
+        assert!((both - 1.3 * 1.3 * 2.0).abs() < 1e-9);
+        assert!((last - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_pause_is_split_from_the_reading_time() {
+        let timing = Timing::default();
+        let tokens = tokenize::tokenize("Stop. Go");
+        let (reading, pause) = timing.reading_and_pause(&tokens[0], tokens.get(1), 60);
+        assert_eq!((reading.as_secs_f64(), pause.as_secs_f64()), (1.0, 1.0));
+
+        // A multiplier below 1 shortens the word rather than pausing negatively
+        let quick = Timing {
+            sentence_end: 0.5,
+            ..Timing::default()
+        };
+        let (reading, pause) = quick.reading_and_pause(&tokens[0], tokens.get(1), 60);
+        assert_eq!((reading.as_secs_f64(), pause), (0.5, Duration::ZERO));
+    }
+
+    #[test]
+    fn ramp_climbs_linearly_to_full_speed() {
+        let timing = Timing::default();
+        let factors: Vec<f64> = (0..7).map(|i| timing.ramp_factor(i)).collect();
+        assert_eq!(factors, [0.5, 0.6, 0.7, 0.8, 0.9, 1.0, 1.0]);
+        let off = Timing {
+            ramp_words: 0,
+            ..Timing::default()
+        };
+        assert_eq!(off.ramp_factor(0), 1.0);
+    }
+
+    #[test]
+    fn problems_name_out_of_range_keys() {
+        let timing = Timing {
+            comma: 0.0,
+            ramp_start: 1.5,
+            long_word_length: 0,
+            ramp_words: MAX_RAMP_WORDS + 1,
+            ..Timing::default()
+        };
+        let keys: Vec<String> = timing.problems().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(
+            keys,
+            ["timing.comma", "timing.ramp_start", "timing.long_word_length", "timing.ramp_words"]
+        );
+        assert!(Timing::default().problems().is_empty());
+    }
+}
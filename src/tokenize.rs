@@ -0,0 +1,220 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A single displayable word with the structural boundaries it begins.
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    /// First word of a sentence (always true for paragraph starts).
+    pub sentence_start: bool,
+    /// First word after a blank line in the source (and the first word overall).
+    pub paragraph_start: bool,
+}
+
+/// Splits `content` on whitespace like `str::split_whitespace`, additionally
+/// recording sentence and paragraph boundaries.
+///
+/// A paragraph starts after a run of whitespace containing two or more
+/// newlines. A sentence starts after a word ending in `.`, `!` or `?`, or
+/// their CJK forms (optionally followed by closing quotes or brackets), unless the next word
+/// begins with a lowercase letter — which keeps "e.g. this" in one sentence.
+pub fn tokenize(content: &str) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token<'_>> = Vec::new();
+    let mut word_start: Option<usize> = None;
+    let mut newlines = 0;
+
+    for (i, c) in content.char_indices() {
+        if c.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                push_token(&mut tokens, &content[start..i], newlines);
+                newlines = 0;
+            }
+            if c == '\n' {
+                newlines += 1;
+            }
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+    if let Some(start) = word_start {
+        push_token(&mut tokens, &content[start..], newlines);
+    }
+
+    tokens
+}
+
+/// Appends `text`, where `newlines` counts line breaks since the previous word.
+fn push_token<'a>(tokens: &mut Vec<Token<'a>>, text: &'a str, newlines: usize) {
+    let paragraph_start = tokens.is_empty() || newlines >= 2;
+    let sentence_start = paragraph_start
+        || (tokens.last().is_some_and(|prev| ends_sentence(prev.text)) && !starts_lowercase(text));
+    tokens.push(Token {
+        text,
+        sentence_start,
+        paragraph_start,
+    });
+}
+
+/// Whether `word` ends with terminal punctuation, ignoring trailing closers.
+pub fn ends_sentence(word: &str) -> bool {
+    let trimmed = word.trim_end_matches(['"', '\'', ')', ']', '”', '’', '»', '」', '』', '）']);
+    trimmed.ends_with(['.', '!', '?', '…', '。', '！', '？'])
+}
+
+/// Whether `word` ends with a comma, semicolon, colon or dash, ignoring
+/// trailing closers.
+pub fn ends_clause(word: &str) -> bool {
+    let trimmed = word.trim_end_matches(['"', '\'', ')', ']', '”', '’', '»', '」', '』', '）']);
+    trimmed.ends_with([',', ';', ':', '—', '–', '、', '，', '；', '：'])
+}
+
+fn starts_lowercase(word: &str) -> bool {
+    word.chars()
+        .find(|c| c.is_alphabetic())
+        .is_some_and(|c| c.is_lowercase())
+}
+
+/// Splits `word` into parts at most `max_width` columns wide, breaking after
+/// a hyphen, underscore or slash where one is close enough to the limit,
+/// else between syllables, else wherever the limit falls. Every part but the
+/// last leaves a column free for the continuation mark shown after it. A
+/// `max_width` of 0 leaves every word whole.
+pub fn split_long_word(word: &str, max_width: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = word;
+    while max_width > 0 && rest.width() > max_width {
+        let end = break_point(rest, max_width.saturating_sub(1).max(1));
+        // A single grapheme wider than the limit can't be split further
+        if end == 0 || end == rest.len() {
+            break;
+        }
+        parts.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Byte offset to end the first part of `text` at.
+fn break_point(text: &str, max_width: usize) -> usize {
+    let graphemes: Vec<(usize, &str)> = text.grapheme_indices(true).collect();
+    let mut fits: usize = 0;
+    let mut width = 0;
+    for (_, grapheme) in &graphemes {
+        width += grapheme.width();
+        if width > max_width {
+            break;
+        }
+        fits += 1;
+    }
+    let fits = fits.max(1);
+
+    // Break after grapheme `i - 1`, keeping parts at least half the limit
+    let candidates = || (fits.div_ceil(2)..=fits).rev();
+    let separator = candidates().find(|&i| graphemes[i - 1].1.ends_with(['-', '_', '/', '–', '—']));
+    let letters = |i: usize| {
+        let letter = |j: Option<usize>| graphemes.get(j?).map(|(_, g)| *g);
+        [i.checked_sub(2), Some(i - 1), Some(i), Some(i + 1)].map(letter)
+    };
+    let syllable = |clear: bool| candidates().find(|&i| starts_syllable(letters(i), clear));
+    let end = separator
+        .or_else(|| syllable(true))
+        .or_else(|| syllable(false))
+        .unwrap_or(fits);
+    graphemes.get(end).map_or(text.len(), |&(offset, _)| offset)
+}
+
+/// Whether a syllable may start at the third of four graphemes: a consonant
+/// followed by a vowel, after another letter. A `clear` start also has at
+/// most one consonant between it and the vowel before, as in "uni|ver|sity"
+/// or "syl|lable"; after a longer cluster ("Donaudampfschiff|fahrt") the
+/// break is more of a guess.
+fn starts_syllable([before, previous, current, next]: [Option<&str>; 4], clear: bool) -> bool {
+    let is_letter = |g: Option<&str>| g.and_then(|g| g.chars().next()).is_some_and(char::is_alphabetic);
+    let is_vowel = |g: Option<&str>| {
+        g.and_then(|g| g.chars().next()).is_some_and(|c| {
+            c.to_lowercase().any(|c| "aeiouyàáâãäåèéêëìíîïòóôõöùúûüýæøœаеёиоуыэюя".contains(c))
+        })
+    };
+    let is_consonant = |g: Option<&str>| is_letter(g) && !is_vowel(g);
+    is_letter(previous)
+        && is_consonant(current)
+        && is_vowel(next)
+        && (!clear || is_vowel(previous) || (is_consonant(previous) && is_vowel(before)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(content: &str) -> Vec<(&str, bool, bool)> {
+        tokenize(content)
+            .iter()
+            .map(|token| (token.text, token.sentence_start, token.paragraph_start))
+            .collect()
+    }
+
+    #[test]
+    fn sentences_start_after_terminal_punctuation() {
+        assert_eq!(
+            starts("He said \"stop.\" Then left, e.g. quickly! 好。 Done"),
+            [
+                ("He", true, true),
+                ("said", false, false),
+                ("\"stop.\"", false, false),
+                ("Then", true, false),
+                ("left,", false, false),
+                ("e.g.", false, false),
+                ("quickly!", false, false),
+                ("好。", true, false),
+                ("Done", true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn paragraphs_start_after_a_blank_line() {
+        assert_eq!(
+            starts("one\ntwo\n  \n\tthree"),
+            [("one", true, true), ("two", false, false), ("three", true, true)]
+        );
+    }
+
+    #[test]
+    fn long_words_break_after_separators() {
+        assert_eq!(
+            split_long_word("/usr/local/share/applications/firefox.desktop", 20),
+            ["/usr/local/share/", "applications/", "firefox.desktop"]
+        );
+        assert_eq!(
+            split_long_word("state-of-the-art-technology-company", 20),
+            ["state-of-the-art-", "technology-company"]
+        );
+        assert_eq!(
+            split_long_word("snake_case_identifiers_are_long", 20),
+            ["snake_case_", "identifiers_are_long"]
+        );
+    }
+
+    #[test]
+    fn long_words_without_separators_break_between_syllables() {
+        assert_eq!(split_long_word("university", 5), ["uni", "ver", "sity"]);
+        assert_eq!(split_long_word("syllable", 6), ["syl", "lable"]);
+        assert_eq!(
+            split_long_word("Donaudampfschifffahrtsgesellschaftskapitän", 20),
+            ["Donaudampfschiff", "fahrtsgesellschafts", "kapitän"]
+        );
+    }
+
+    #[test]
+    fn parts_leave_room_for_the_continuation_mark() {
+        // No syllables to go by, so each part fills all but the last column
+        assert_eq!(split_long_word("1234567890123456", 8), ["1234567", "8901234", "56"]);
+        assert_eq!(split_long_word("12345678", 8), ["12345678"]);
+        assert_eq!(split_long_word("anything", 0), ["anything"]);
+        // Graphemes wider than the room left are shown whole, never leaving an empty part
+        assert_eq!(split_long_word("漢", 1), ["漢"]);
+        assert_eq!(split_long_word("😀😀", 1), ["😀", "😀"]);
+        assert_eq!(split_long_word("漢字漢字", 2), ["漢", "字", "漢", "字"]);
+    }
+}
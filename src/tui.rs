@@ -4,11 +4,12 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal, TerminalOptions, Viewport};
-use std::io::{self, stdout};
+use std::io::{self, stdout, IsTerminal};
 
 pub type Tui = Terminal<CrosstermBackend<io::Stdout>>;
 
 pub fn init(is_inline: bool) -> Result<Tui> {
+    ensure_controlling_terminal()?;
     enable_raw_mode()?;
     if !is_inline {
         execute!(stdout(), EnterAlternateScreen)?;
@@ -27,10 +28,38 @@ pub fn init(is_inline: bool) -> Result<Tui> {
     Ok(terminal)
 }
 
+/// When text is piped in, stdin is not a terminal and crossterm falls back to
+/// `/dev/tty` for raw mode and key events. Check it up front so a missing
+/// controlling terminal is reported clearly instead of as a raw-mode failure.
+fn ensure_controlling_terminal() -> Result<()> {
+    if io::stdin().is_terminal() {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("stdin is not a terminal and /dev/tty is unavailable: {e}"),
+                )
+            })?;
+    }
+    Ok(())
+}
+
 pub fn restore(is_inline: bool, terminal: &mut Tui) -> Result<()> {
     disable_raw_mode()?;
     if !is_inline {
         execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    } else {
+        // Park the cursor below the inline viewport so later output doesn't overwrite it
+        let viewport = terminal.get_frame().area();
+        terminal.set_cursor_position((0, viewport.bottom().saturating_sub(1)))?;
+        println!();
     }
     Ok(())
 }
@@ -1,10 +1,24 @@
+use crate::{
+    bidi,
+    document::Emphasis,
+    keys::{Action, Keymap},
+    stats::SessionStats,
+    theme::Theme,
+};
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     prelude::*,
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Clear, LineGauge, Paragraph},
+    widgets::{Block, BorderType, Borders, Clear, LineGauge, Paragraph, Wrap},
 };
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Marks a part of a long word that continues in the next flash. One column
+/// wide, which `tokenize::split_long_word` leaves room for.
+const CONTINUATION: &str = "…";
 
 pub struct UIConstraints {
     pub constraints: Vec<Constraint>,
@@ -203,43 +217,276 @@ fn brighten_color_ui(color: Color) -> Color {
     }
 }
 
-/// Find the optimal focus point (character index) for a word
-/// Uses a heuristic similar to Spritz speed reading
-fn find_focus_point(word: &str) -> usize {
-    let len = word.chars().count();
+/// Words surrounding the current one, shown while paused so the reader can
+/// re-orient. `current` indexes into `words`.
+pub struct Context<'a> {
+    pub words: Vec<&'a str>,
+    pub current: usize,
+}
+
+fn context_styles(theme: &Theme) -> (Style, Style) {
+    let current = theme.focus_style().add_modifier(Modifier::UNDERLINED);
+    (current, Style::default().fg(theme.context))
+}
+
+/// A single line centred on the current word, filled outwards with as many
+/// neighbouring words as fit in `width`. Right-to-left text puts earlier
+/// words on the right.
+fn context_line<'a>(context: &Context<'a>, width: usize, theme: &Theme, reorder_rtl: bool) -> Line<'a> {
+    let (current_style, other_style) = context_styles(theme);
+    let Some(&current) = context.words.get(context.current) else {
+        return Line::default();
+    };
+
+    let current_len = display(current, reorder_rtl).width();
+    let side = width.saturating_sub(current_len) / 2;
+
+    // How many neighbours fit on one side, nearest first
+    let fitting = |words: &mut dyn Iterator<Item = &&str>| {
+        let mut len = 0;
+        words
+            .take_while(|word| {
+                len += display(word, reorder_rtl).width() + 1;
+                len <= side
+            })
+            .count()
+    };
+    let before = fitting(&mut context.words[..context.current].iter().rev());
+    let after = fitting(&mut context.words[context.current + 1..].iter());
+    let words: Vec<(&str, Style)> = (context.current - before..=context.current + after)
+        .map(|i| {
+            let style = if i == context.current { current_style } else { other_style };
+            (context.words[i], style)
+        })
+        .collect();
+
+    let rtl = reads_rtl(context);
+    let mut spans = line_spans(&words, rtl, reorder_rtl);
+    // Pad so that the current word sits in the middle. A terminal that
+    // reorders by itself shows the words after it on its left.
+    let current_span = spans
+        .iter()
+        .position(|span| span.style == current_style)
+        .unwrap_or(0);
+    let lead = if rtl && !reorder_rtl {
+        &spans[current_span + 1..]
+    } else {
+        &spans[..current_span]
+    };
+    let lead_len: usize = lead.iter().map(Span::width).sum();
+    spans.insert(0, Span::raw(" ".repeat(side.saturating_sub(lead_len))));
+    Line::from(spans)
+}
+
+/// Draws the surrounding paragraph word-wrapped into `area`, scrolled so the
+/// current word stays near the vertical middle. Right-to-left text is
+/// right-aligned and runs from the right.
+fn render_context_pane(frame: &mut Frame, area: Rect, context: &Context, theme: &Theme, reorder_rtl: bool) {
+    let dim_style = theme.preview_style();
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .border_style(dim_style)
+        .title(Span::styled(" Context ", dim_style))
+        .title_alignment(Alignment::Center);
+
+    // Keep the text to a readable measure, centred in the pane
+    let inner = block.inner(area);
+    let text_width = inner.width.min(72);
+    let text_area = Rect {
+        x: inner.x + (inner.width - text_width) / 2,
+        width: text_width,
+        ..inner
+    };
+    frame.render_widget(block, area);
+
+    let (current_style, other_style) = context_styles(theme);
+    let rtl = reads_rtl(context);
+    let alignment = if rtl { Alignment::Right } else { Alignment::Left };
+    let mut lines: Vec<Line> = Vec::new();
+    let mut line_words: Vec<(&str, Style)> = Vec::new();
+    let mut line_len = 0;
+    let mut current_line = 0;
+    for (i, &word) in context.words.iter().enumerate() {
+        let len = display(word, reorder_rtl).width();
+        if line_len > 0 && line_len + 1 + len > text_width as usize {
+            let spans = line_spans(&std::mem::take(&mut line_words), rtl, reorder_rtl);
+            lines.push(Line::from(spans).alignment(alignment));
+            line_len = 0;
+        }
+        if line_len > 0 {
+            line_len += 1;
+        }
+        let style = if i == context.current {
+            current_line = lines.len();
+            current_style
+        } else {
+            other_style
+        };
+        line_words.push((word, style));
+        line_len += len;
+    }
+    lines.push(Line::from(line_spans(&line_words, rtl, reorder_rtl)).alignment(alignment));
+
+    let scroll = current_line.saturating_sub(text_area.height as usize / 2) as u16;
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), text_area);
+}
+
+/// Spans for one line of styled words separated by spaces. With
+/// `reorder_rtl` the line is put in display order as a whole, reading right
+/// to left if `rtl`; otherwise it stays in logical order for the terminal
+/// to reorder.
+fn line_spans(words: &[(&str, Style)], rtl: bool, reorder_rtl: bool) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    if !reorder_rtl {
+        for (i, &(word, style)) in words.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::styled(word.to_string(), style));
+        }
+        return spans;
+    }
+
+    let texts: Vec<&str> = words.iter().map(|&(word, _)| word).collect();
+    let mut owner = None;
+    for (word, grapheme) in bidi::visual_line(&texts, rtl) {
+        match spans.last_mut() {
+            Some(span) if word == owner => span.content.to_mut().push_str(&grapheme),
+            _ => {
+                let style = word.map_or(Style::default(), |i| words[i].1);
+                spans.push(Span::styled(grapheme, style));
+            }
+        }
+        owner = word;
+    }
+    spans
+}
+
+/// Find the optimal focus point (grapheme index) for a word of `len`
+/// graphemes. Uses a heuristic similar to Spritz speed reading
+fn find_focus_point(len: usize) -> usize {
     match len {
         1 => 0,
         2..=5 => 1,
         6..=9 => 2,
         10..=13 => 3,
-        _ => 4,
+        _ => len * 3 / 10,
+    }
+}
+
+/// Splits a word around its focus grapheme into what is drawn left of it,
+/// the focus, and what is drawn right of it. The focus is counted from the
+/// word's logical start, so in a right-to-left word it sits near the right
+/// end. Working in grapheme clusters keeps accents and emoji sequences whole.
+fn split_at_focus(word: &str, reorder_rtl: bool) -> (String, String, String) {
+    let graphemes: Vec<(usize, String)> = if reorder_rtl {
+        bidi::visual_graphemes(word)
+    } else {
+        word.graphemes(true).map(str::to_string).enumerate().collect()
+    };
+    // In a chunk of several words, a focus landing on a space moves on to
+    // the next word
+    let mut focus = find_focus_point(graphemes.len());
+    while graphemes
+        .iter()
+        .any(|(i, grapheme)| *i == focus && grapheme.trim().is_empty())
+    {
+        focus += 1;
     }
+    let Some(position) = graphemes.iter().position(|&(i, _)| i == focus) else {
+        return (word.to_string(), String::new(), String::new());
+    };
+    let join = |graphemes: &[(usize, String)]| {
+        graphemes.iter().map(|(_, grapheme)| grapheme.as_str()).collect::<String>()
+    };
+    (
+        join(&graphemes[..position]),
+        graphemes[position].1.clone(),
+        join(&graphemes[position + 1..]),
+    )
+}
+
+/// A word as drawn: shaped and in display order when reordering is on.
+fn display(word: &str, reorder_rtl: bool) -> Cow<'_, str> {
+    if reorder_rtl {
+        Cow::Owned(bidi::visual(word))
+    } else {
+        Cow::Borrowed(word)
+    }
+}
+
+/// Whether the text around the current word reads right to left, going by
+/// the current word or, if it has no letters with a direction, the nearest
+/// word after it that does.
+fn reads_rtl(context: &Context) -> bool {
+    context.words[context.current.min(context.words.len())..]
+        .iter()
+        .find_map(|word| bidi::is_rtl(word))
+        .unwrap_or(false)
+}
+
+/// Display settings fixed for the whole session.
+pub struct DisplayOptions {
+    pub constraints: UIConstraints,
+    pub is_inline: bool,
+    pub border_color: Option<Color>,
+    pub enable_animations: bool,
+    pub show_border: bool,
+    pub show_progress_bar: bool,
+    pub reorder_rtl: bool,
+}
+
+/// What a single frame of the reader shows.
+pub struct WordView<'a> {
+    pub word: &'a str,
+    /// The word is a part of a longer one that continues in the next flash.
+    pub continues: bool,
+    pub emphasis: Emphasis,
+    pub preview_words: &'a [&'a str],
+    pub context: Option<&'a Context<'a>>,
+    pub current_word: usize,
+    pub total_words: usize,
+    pub wpm: u64,
+    pub status: Option<&'a str>,
+    pub is_paused: bool,
+    pub border_progress: Option<f32>,
+    pub time_ms: u64,
 }
 
 pub fn render_word_display(
     frame: &mut Frame,
-    word: &str,
-    preview_words: &[&str],
-    current_word: usize,
-    total_words: usize,
-    is_paused: bool,
-    constraints: &UIConstraints,
-    is_inline: bool,
-    border_color: Option<Color>,
-    border_progress: Option<f32>,
-    time_ms: u64,
-    progress_bar_color: Color,
-    focus_color: Color,
-    enable_animations: bool,
-    show_border: bool,
-    show_progress_bar: bool,
+    view: &WordView,
+    options: &DisplayOptions,
+    theme: &Theme,
 ) -> Rect {
+    let &WordView {
+        word,
+        continues,
+        emphasis,
+        preview_words,
+        context,
+        current_word,
+        total_words,
+        wpm,
+        status,
+        is_paused,
+        border_progress,
+        time_ms,
+    } = view;
+    let DisplayOptions {
+        ref constraints,
+        is_inline,
+        border_color,
+        enable_animations,
+        show_border,
+        show_progress_bar,
+        reorder_rtl,
+    } = *options;
     let area = frame.area();
 
     // If inline mode and border is enabled, render a border
-    let inner_area = if is_inline && show_border && border_color.is_some() {
-        let base_border_color = border_color.unwrap();
-
+    let visible_border = border_color.filter(|_| is_inline && show_border);
+    let inner_area = if let Some(base_border_color) = visible_border {
         let inner = Rect {
             x: area.x + 1,
             y: area.y + 1,
@@ -273,42 +520,67 @@ pub fn render_word_display(
         .constraints(constraints.constraints.clone())
         .split(inner_area);
 
-    let focus_idx = find_focus_point(word);
-    let chars: Vec<char> = word.chars().collect();
-
-    // Split word into before, focus, and after
-    let before: String = chars.iter().take(focus_idx).collect();
-    let focus = chars
-        .get(focus_idx)
-        .map(|c| c.to_string())
-        .unwrap_or_default();
-    let after: String = chars.iter().skip(focus_idx + 1).collect();
+    let (left, focus, right) = split_at_focus(word, reorder_rtl);
 
-    // Calculate padding to center the focus character
+    // Pad so the focus grapheme starts on the centre column. Padding is in
+    // terminal columns, so wide (CJK, emoji) text lines up too
     let term_width = chunks[1].width as usize;
     let focus_position = term_width / 2;
-    let padding_left = if focus_position > before.len() {
-        focus_position - before.len()
-    } else {
-        0
-    };
+    let mut padding_left = focus_position.saturating_sub(left.width());
 
-    // Build the line: current word + preview words appended inline
-    let dim_style = Style::default().fg(Color::DarkGray);
+    // Build the line: current word + preview words in reading order
+    let dim_style = theme.preview_style();
+    let mut word_style = Style::default().fg(theme.word);
+    if emphasis.bold {
+        word_style = word_style.add_modifier(Modifier::BOLD);
+    }
+    if emphasis.italic {
+        word_style = word_style.add_modifier(Modifier::ITALIC);
+    }
+    let upcoming: Vec<&str> = std::iter::once(word).chain(preview_words.iter().copied()).collect();
+    let rtl = reads_rtl(&Context { words: upcoming, current: 0 });
     let mut spans = vec![
-        Span::raw(" ".repeat(padding_left)),
-        Span::raw(&before),
-        Span::styled(&focus, Style::default().fg(focus_color).bold()),
-        Span::raw(&after),
+        Span::styled(left, word_style),
+        Span::styled(focus, word_style.patch(theme.focus_style())),
+        Span::styled(right, word_style),
     ];
-    for &preview_word in preview_words.iter() {
-        spans.push(Span::styled(format!(" {}", preview_word), dim_style));
+    // The glyph goes where the word continues, after it in reading order
+    if continues && rtl {
+        padding_left = padding_left.saturating_sub(CONTINUATION.width());
+        spans.insert(0, Span::styled(CONTINUATION, dim_style));
+    } else if continues {
+        spans.push(Span::styled(CONTINUATION, dim_style));
     }
-    let line = Line::from(spans);
+    let previews = preview_words.iter().map(|&preview| display(preview, reorder_rtl));
+    if rtl {
+        // Upcoming words continue to the left, in the padding
+        let mut room = padding_left;
+        for preview in previews {
+            let len = preview.width() + 1;
+            if len > room {
+                break;
+            }
+            room -= len;
+            spans.insert(0, Span::styled(format!("{} ", preview), dim_style));
+        }
+        spans.insert(0, Span::raw(" ".repeat(room)));
+    } else {
+        spans.insert(0, Span::raw(" ".repeat(padding_left)));
+        spans.extend(previews.map(|preview| Span::styled(format!(" {}", preview), dim_style)));
+    }
+    let line = match context {
+        // Inline has no room for a pane, so the word line widens to the sentence
+        Some(context) if is_inline => context_line(context, term_width, theme, reorder_rtl),
+        _ => Line::from(spans),
+    };
 
     let paragraph = Paragraph::new(line).alignment(Alignment::Left);
     frame.render_widget(paragraph, chunks[1]);
 
+    if let Some(context) = context.filter(|_| !is_inline) {
+        render_context_pane(frame, chunks[3], context, theme, reorder_rtl);
+    }
+
     // Render progress bar if enabled
     let progress_chunk_idx = 2;
     let progress_area = if show_progress_bar {
@@ -316,7 +588,7 @@ pub fn render_word_display(
 
         // Apply pulsing effect if animations are enabled
         let fg_color = if is_paused {
-            Color::Rgb(255, 165, 0) // Orange for paused
+            theme.paused
         } else if enable_animations {
             // Pulsing effect for the progress bar color (0.9-1.0 intensity)
             let pulse_cycle = 1500.0; // 1.5 second cycle
@@ -324,7 +596,7 @@ pub fn render_word_display(
             let pulse_intensity = 0.9 + (pulse_phase.sin() * 0.1);
 
             // Apply pulsing to config color
-            match progress_bar_color {
+            match theme.progress_bar {
                 Color::Rgb(r, g, b) => {
                     Color::Rgb(
                         (r as f64 * pulse_intensity) as u8,
@@ -332,14 +604,20 @@ pub fn render_word_display(
                         (b as f64 * pulse_intensity) as u8,
                     )
                 }
-                _ => progress_bar_color, // Use config color as-is for named colors
+                color => color, // Use config color as-is for named colors
             }
         } else {
-            progress_bar_color // No animations - use config color as-is
+            theme.progress_bar // No animations - use config color as-is
         };
 
         let label_prefix = if is_paused { "⏸ " } else { "▶ " };
-        let progress_label = format!("{}{}/{}", label_prefix, current_word + 1, total_words);
+        let progress_label = format!(
+            "{}{}/{} · {} wpm",
+            label_prefix,
+            current_word + 1,
+            total_words,
+            wpm
+        );
 
         // Custom progress bar with transparent background (respects terminal)
         let progress_bar = LineGauge::default()
@@ -348,21 +626,38 @@ pub fn render_word_display(
                     .fg(fg_color)
                     .add_modifier(Modifier::BOLD),
             )
-            .unfilled_style(
-                Style::default()
-                    .fg(Color::DarkGray),
-            )
+            .unfilled_style(theme.preview_style())
             .line_set(symbols::line::THICK)
             .ratio(progress)
             .label(progress_label);
 
-        frame.render_widget(progress_bar, chunks[progress_chunk_idx]);
-        chunks[progress_chunk_idx]
+        // The gauge takes the first row; the chapter title, if any, the second
+        let gauge_area = Rect {
+            height: 1.min(chunks[progress_chunk_idx].height),
+            ..chunks[progress_chunk_idx]
+        };
+        frame.render_widget(progress_bar, gauge_area);
+
+        gauge_area
     } else {
         // Return empty area if progress bar is disabled
         Rect::default()
     };
 
+    // Status text (chapter title, search prompt or results) sits below the gauge
+    if let Some(text) = status {
+        let chunk = chunks[progress_chunk_idx];
+        let title_area = Rect {
+            y: chunk.y + 1,
+            height: chunk.height.saturating_sub(1),
+            ..chunk
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(text, dim_style))).alignment(Alignment::Center),
+            title_area,
+        );
+    }
+
     // Return progress bar area for effects
     progress_area
 }
@@ -372,13 +667,20 @@ pub fn render_word_display(
 /// remain visible around it without bleeding into the overlay.
 /// `scroll` is a raw offset from app state — it is clamped here at render
 /// time because the maximum depends on `area.height`, which is only known
-/// inside the draw closure.
-pub fn render_help_popup(frame: &mut Frame, border_color: Color, scroll: u16, seek_step: usize) {
+/// inside the draw closure. `rows` are generated from the active keymap so
+/// remapped keys show up as bound.
+pub fn render_help_popup(
+    frame: &mut Frame,
+    theme: &Theme,
+    scroll: u16,
+    rows: &[(String, String)],
+    keymap: &Keymap,
+) {
     let area = frame.area();
 
     // Popup dimensions — clamp to available terminal space
-    let popup_width = 46u16.min(area.width);
-    let popup_height = 14u16.min(area.height);
+    let popup_width = 56u16.min(area.width);
+    let popup_height = 18u16.min(area.height);
 
     let popup_x = area.x + area.width.saturating_sub(popup_width) / 2;
     let popup_y = area.y + area.height.saturating_sub(popup_height) / 2;
@@ -396,15 +698,16 @@ pub fn render_help_popup(frame: &mut Frame, border_color: Color, scroll: u16, se
     // Separator fills the inner width (popup minus two border chars)
     let sep_width = popup_width.saturating_sub(4) as usize;
 
+    let border_color = theme.border;
     let key_style = Style::default()
-        .fg(Color::Yellow)
+        .fg(theme.help_key)
         .add_modifier(Modifier::BOLD);
     let header_style = Style::default()
-        .fg(Color::Gray)
+        .fg(theme.context)
         .add_modifier(Modifier::ITALIC);
-    let dim_style = Style::default().fg(Color::DarkGray);
+    let dim_style = theme.preview_style();
 
-    let lines = vec![
+    let mut lines = vec![
         Line::from(vec![
             Span::styled(format!("  {:<14}", "Key"), header_style),
             Span::styled("Action", header_style),
@@ -413,33 +716,22 @@ pub fn render_help_popup(frame: &mut Frame, border_color: Color, scroll: u16, se
             format!("  {}", "─".repeat(sep_width)),
             Style::default().fg(border_color),
         )),
-        Line::from(vec![
-            Span::styled(format!("  {:<14}", "q / Esc"), key_style),
-            Span::raw("Quit"),
-        ]),
-        Line::from(vec![
-            Span::styled(format!("  {:<14}", "Space"), key_style),
-            Span::raw("Pause / Resume"),
-        ]),
-        Line::from(vec![
-            Span::styled(format!("  {:<14}", "h / ←"), key_style),
-            Span::raw(format!("Rewind {} words", seek_step)),
-        ]),
-        Line::from(vec![
-            Span::styled(format!("  {:<14}", "l / →"), key_style),
-            Span::raw(format!("Fast-forward {} words", seek_step)),
-        ]),
-        Line::from(vec![
-            Span::styled(format!("  {:<14}", "?"), key_style),
-            Span::raw("Toggle this help"),
-        ]),
-        Line::from(vec![
-            Span::styled(format!("  {:<14}", "j / k / ↑↓"), key_style),
-            Span::raw("Scroll help"),
-        ]),
-        Line::from(""),
-        Line::from(Span::styled("  Press ? or Esc to close", dim_style)),
     ];
+    for (keys, description) in rows {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<14}", keys), key_style),
+            Span::raw(description.as_str()),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!(
+            "  Press {} or {} to close",
+            keymap.keys_label(Action::Help),
+            keymap.keys_label(Action::Quit)
+        ),
+        dim_style,
+    )));
 
     // Clamp scroll so we never show empty space below the last line.
     // inner_height = popup height minus top and bottom border rows.
@@ -463,16 +755,18 @@ pub fn render_help_popup(frame: &mut Frame, border_color: Color, scroll: u16, se
         .border_style(Style::default().fg(border_color));
 
     let block = if max_scroll > 0 {
+        let keys = format!(
+            "{}/{}",
+            keymap.primary_key(Action::ScrollDown),
+            keymap.primary_key(Action::ScrollUp)
+        );
         let hint = match (effective_scroll > 0, effective_scroll < max_scroll) {
-            (false, true) => " ↓ j/k ",
-            (true, true) => " ↑↓ j/k ",
-            (true, false) => " ↑ j/k ",
-            _ => "",
+            (false, true) => format!(" ↓ {} ", keys),
+            (true, true) => format!(" ↑↓ {} ", keys),
+            (true, false) => format!(" ↑ {} ", keys),
+            _ => String::new(),
         };
-        base_block.title_bottom(Line::from(Span::styled(
-            hint,
-            Style::default().fg(Color::DarkGray),
-        )))
+        base_block.title_bottom(Line::from(Span::styled(hint, dim_style)))
     } else {
         base_block
     };
@@ -482,3 +776,74 @@ pub fn render_help_popup(frame: &mut Frame, border_color: Color, scroll: u16, se
         popup_area,
     );
 }
+
+/// Renders the end-of-reading summary. Fullscreen gets a centered table;
+/// the 5-row inline viewport gets the same figures on a single line.
+pub fn render_summary(frame: &mut Frame, stats: &SessionStats, theme: &Theme) {
+    let area = frame.area();
+    let rows = stats.summary_rows();
+    frame.render_widget(Clear, area);
+
+    let border_color = theme.border;
+    let label_style = Style::default()
+        .fg(theme.context)
+        .add_modifier(Modifier::ITALIC);
+    let dim_style = theme.preview_style();
+    let hint = Line::from(Span::styled("Press any key to exit", dim_style));
+
+    let compact = area.height < rows.len() as u16 + 6;
+    let lines: Vec<Line> = if compact {
+        let figures: Vec<String> = rows
+            .iter()
+            .map(|(label, value)| format!("{}: {}", label, value))
+            .collect();
+        vec![Line::from(figures.join(" · ")), hint]
+    } else {
+        let mut lines: Vec<Line> = rows
+            .into_iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(format!("  {:<12}", label), label_style),
+                    Span::raw(value),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(hint);
+        lines
+    };
+
+    let (width, height) = if compact {
+        (area.width, area.height)
+    } else {
+        (46u16.min(area.width), (lines.len() as u16 + 2).min(area.height))
+    };
+    let popup_area = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+
+    let block = Block::default()
+        .title(Span::styled(
+            " Finished ",
+            Style::default()
+                .fg(border_color)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Double)
+        .border_style(Style::default().fg(border_color));
+
+    let paragraph = Paragraph::new(lines).block(block);
+    let paragraph = if compact {
+        paragraph
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+    } else {
+        paragraph
+    };
+    frame.render_widget(paragraph, popup_area);
+}